@@ -1,6 +1,11 @@
 use std::hint::black_box;
-use criterion::{criterion_group, criterion_main, Criterion};
-use hmg::{Position, MoveList};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use criterion::{criterion_group, Criterion};
+use hmg::{Position, MoveList, Square, Bitboard};
+
+// set in the copies of this benchmark started by "tables first use"
+const FIRST_USE: &str = "HMG_BENCH_FIRST_USE";
 
 fn run_test_position(position: &mut Position, depth: usize, max_depth: usize){
 
@@ -32,16 +37,66 @@ fn from_start_position(){
     run_test_position(&mut p, 0, 4);
 }
 
+/// one lookup in each table, the first of them in a process is when
+/// the lazy-tables feature builds the tables
+fn lookup_every_table(square: Square, other: Square, board: Bitboard){
+    black_box(hmg::get_orth_moves(square, board));
+    black_box(hmg::get_diag_moves(square, board));
+    black_box(hmg::get_potential_stradler_captures(square, board));
+    black_box(hmg::get_death_squares(square, other));
+    black_box(hmg::get_springer_landing_square(square, other));
+    black_box(hmg::get_springer_captured_square(square, other));
+    black_box(hmg::get_retractor_lookup(square, other));
+}
+
+/// prints how many nanoseconds the first lookups of the process take
+fn first_use(){
+    let start = Instant::now();
+    lookup_every_table(black_box(Square::E4), black_box(Square::C6), black_box(Bitboard(0x0042_0000_1800_2400)));
+    println!("{}", start.elapsed().as_nanos());
+}
+
 fn benchmark(c: &mut Criterion){
 
     c.bench_function("from start position", |b| b.iter(||
         black_box(from_start_position())
     ));
 
+    // mostly table lookups, compare with --features lazy-tables
+    let mut p = Position::from_FEN("unbqkbn1/pppppppp/3PPP2/3r1B2/8/8/PPP3PP/RNBQK1NU w 0 8").unwrap();
+    c.bench_function("generate moves", |b| b.iter(
+        || black_box(&mut p).generate_moves()
+    ));
+
+    // every lookup goes through LazyLock with lazy-tables
+    c.bench_function("table lookups", |b| b.iter(
+        || for (i, square) in Square::ALL.into_iter().enumerate(){
+            lookup_every_table(square, Square::ALL[63 - i], black_box(Bitboard(0x0042_0000_1800_2400)));
+        }
+    ));
+
+    // the const tables are already there, the lazy ones are built here.
+    // each sample is a new process that times only its own first use
+    c.bench_function("tables first use", |b| b.iter_custom(|iters| {
+        let exe = std::env::current_exe().unwrap();
+        (0..iters).map(|_| {
+            let output = Command::new(&exe).env(FIRST_USE, "1").output().unwrap();
+            Duration::from_nanos(String::from_utf8(output.stdout).unwrap().trim().parse().unwrap())
+        }).sum()
+    }));
 }
 
 criterion_group!{name = hmg_bench; 
     config = Criterion::default().sample_size(10);
     targets = benchmark
 }
-criterion_main!(hmg_bench);
+// criterion_main! with the first use copies cut short
+fn main(){
+    if std::env::var_os(FIRST_USE).is_some(){
+        first_use();
+        return;
+    }
+
+    hmg_bench();
+    Criterion::default().configure_from_args().final_summary();
+}
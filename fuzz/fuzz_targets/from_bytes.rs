@@ -0,0 +1,22 @@
+#![no_main]
+
+// any bytes have to either be rejected or read into a consistent
+// position that packs back into exactly the same bytes
+
+use hmg::{Position, PositionIssue};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+
+    let Ok(position) = Position::from_bytes(bytes) else{
+        return;
+    };
+
+    assert!(!matches!(position.validate(), Err(issues) if issues.contains(&PositionIssue::Inconsistent)));
+    assert_eq!(position.to_bytes().as_slice(), bytes);
+
+    // FEN leaves out the rules, which are part of the hash
+    let mut reread = Position::from_FEN(&position.to_FEN()).unwrap();
+    reread.set_rules(position.rules());
+    assert_eq!(reread.zobrist_hash(), position.zobrist_hash());
+});
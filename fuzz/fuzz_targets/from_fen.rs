@@ -0,0 +1,30 @@
+#![no_main]
+
+// any string has to either be rejected or read into a consistent
+// position that writes back out to the same FEN, and move generation
+// can't panic on anything validate accepts
+
+use hmg::{Position, PositionIssue};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|fen: &str| {
+
+    let Ok(position) = Position::from_FEN(fen) else{
+        return;
+    };
+
+    assert!(!matches!(position.validate(), Err(issues) if issues.contains(&PositionIssue::Inconsistent)), "{fen}");
+
+    let written = position.to_FEN();
+    let reread = Position::from_FEN(&written).expect(&written);
+
+    assert_eq!(reread.to_FEN(), written);
+    assert_eq!(reread.zobrist_hash(), position.zobrist_hash());
+
+    if position.validate().is_ok(){
+        let mut position = position;
+        for m in position.generate_moves(){
+            position.is_move_legal(m);
+        }
+    }
+});
@@ -0,0 +1,62 @@
+#![no_main]
+
+// plays a sequence of legal moves picked by the input, checking the
+// position after every move and that unmaking all of them gets back
+// to where it started
+
+use hmg::{Position, PositionIssue};
+use libfuzzer_sys::fuzz_target;
+
+mod positions;
+
+// validate reports Inconsistent whatever else it finds
+fn consistent(position: &Position) -> bool{
+    !matches!(position.validate(), Err(issues) if issues.contains(&PositionIssue::Inconsistent))
+}
+
+fuzz_target!(|data: &[u8]| {
+
+    let Some((first, choices)) = data.split_first() else{
+        return;
+    };
+
+    let fen = positions::FENS[*first as usize % positions::FENS.len()];
+    let rules = positions::RULES[*first as usize / positions::FENS.len() % positions::RULES.len()];
+
+    let mut position = Position::from_FEN(fen).unwrap();
+    position.set_rules(rules);
+    let start_hash = position.zobrist_hash();
+
+    let mut played = Vec::new();
+
+    for choice in choices{
+
+        let legal: Vec<_> = position.generate_moves()
+            .into_iter()
+            .filter(|m| position.clone().is_move_legal(*m))
+            .collect();
+
+        if legal.is_empty(){
+            break;
+        }
+
+        let m = legal[*choice as usize % legal.len()];
+        position.make_move(m);
+        played.push(m);
+
+        assert!(consistent(&position), "{fen} {rules:?} {played:?}");
+
+        // the incremental hash has to match the one of the same position read from scratch
+        let mut reread = Position::from_FEN(&position.to_FEN()).unwrap();
+        reread.set_rules(rules);
+        assert_eq!(position.zobrist_hash(), reread.zobrist_hash(), "{fen} {rules:?} {played:?}");
+    }
+
+    while let Some(m) = played.pop(){
+        position.unmake_move(m);
+        assert!(consistent(&position), "{fen} {rules:?} {played:?} {m:?}");
+    }
+
+    assert_eq!(position.zobrist_hash(), start_hash);
+    assert_eq!(position.to_FEN(), Position::from_FEN(fen).unwrap().to_FEN());
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+// parse_move has to cope with any string, and whatever it finds has
+// to be one of the moves generated for the position
+
+use hmg::Position;
+use libfuzzer_sys::fuzz_target;
+
+mod positions;
+
+fuzz_target!(|data: (u8, &str)| {
+
+    let (index, notation) = data;
+    let mut position = Position::from_FEN(positions::FENS[index as usize % positions::FENS.len()]).unwrap();
+    position.set_rules(positions::RULES[index as usize / positions::FENS.len() % positions::RULES.len()]);
+
+    if let Some(m) = position.parse_move(notation){
+        assert!(position.generate_moves().into_iter().any(|g| g == m));
+        assert!(m.to_string().eq_ignore_ascii_case(notation.trim()));
+    }
+});
@@ -0,0 +1,18 @@
+use hmg::Rules;
+
+// starting points for the targets that need a valid position,
+// the start position plus a few with every kind of capture available.
+// the side not to play must not be in check, is_move_legal assumes
+// both kings are on the board
+pub const FENS: [&str; 7] = [
+    "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+    "unbqkbn1/pppppppp/3PPP2/3r1B2/8/8/PPP3PP/RNBQK1NU w 0 8",
+    "k7/7U/2K5/8/8/8/8/8 w 0 1",
+    "1k6/8/3p4/2pRp3/3p4/8/2N1Q3/4K3 w 0 1",
+    "4k3/8/2r1b3/3P4/1n1B1q2/8/3U4/4K3 w 0 1",
+    "r3k3/8/8/8/8/8/8/3K3R w 0 1",
+    "n3k3/8/2p1N1b1/8/p3P3/1p6/8/N2K3B b 0 1",
+];
+
+// every position is played under each of these
+pub const RULES: [Rules; 2] = [Rules::HYPERCHESS, Rules::ULTIMA];
@@ -0,0 +1,61 @@
+/* C interface to the hmg move generator, generated by `hmg header`.
+   squares are 0 (A1) to 63 (H8) and moves are the u32 encoding used by
+   the crate, they are only meaningful for the position they came from */
+
+#ifndef HMG_H
+#define HMG_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define HMG_API_VERSION 1
+
+typedef struct HmgPosition HmgPosition;
+
+/* version of this interface, HMG_API_VERSION of the header it was built with */
+uint32_t hmg_api_version(void);
+
+/* the starting position, free with hmg_position_free */
+HmgPosition *hmg_position_start(void);
+
+/* the position described by fen, NULL if it can't be read or isn't valid.
+   free with hmg_position_free */
+HmgPosition *hmg_position_from_fen(const char *fen);
+
+/* frees a position, NULL is ignored */
+void hmg_position_free(HmgPosition *position);
+
+/* writes up to capacity legal moves into moves and returns the number of
+   legal moves, which is more than capacity if some didn't fit */
+size_t hmg_legal_moves(HmgPosition *position, uint32_t *moves, size_t capacity);
+
+/* plays move, 0 on success and -1 if it isn't legal in the position */
+int hmg_make_move(HmgPosition *position, uint32_t move);
+
+/* takes back the last move played with hmg_make_move, 0 on success and
+   -1 if there is none */
+int hmg_unmake_move(HmgPosition *position);
+
+/* FEN letter of the piece on square, uppercase for white, '.' if it is
+   empty and 0 if square is off the board */
+char hmg_piece_at(const HmgPosition *position, uint8_t square);
+
+/* writes the position as FEN into buffer, cut short to fit capacity with
+   the terminating 0, and returns the length of the whole FEN like snprintf */
+size_t hmg_to_fen(const HmgPosition *position, char *buffer, size_t capacity);
+
+/* square move starts from, 64 if it isn't a move */
+uint8_t hmg_move_from(uint32_t move);
+
+/* square move ends on, 64 if it isn't a move */
+uint8_t hmg_move_to(uint32_t move);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,288 @@
+use crate::position::Position;
+use crate::r#move::Move;
+use crate::record::{GameRecord, GameResult};
+use crate::types::Color;
+use crate::utils::PRNG;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::error;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 8] = b"HMGBOOK1";
+const DEFAULT_SEED: u64 = 3262394871;
+
+#[derive(Debug)]
+pub struct ReadBookError{}
+impl From<io::Error> for ReadBookError{
+    fn from(_value: io::Error) -> Self{
+        ReadBookError{}
+    }
+}
+impl fmt::Display for ReadBookError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading opening book")
+    }
+}
+impl error::Error for ReadBookError{}
+
+/// Opening book keyed by zobrist hash
+///
+/// each position holds a list of moves (stored as the packed u32 of Move)
+/// with a weight, moves are picked at random in proportion to their weight
+///
+/// on disk the book is the 8 byte magic "HMGBOOK1" followed by
+/// 16 byte entries sorted by hash: hash (u64), move (u32), weight (u32),
+/// all little endian
+pub struct OpeningBook{
+    entries: HashMap<u64, Vec<(u32, u32)>>,
+    rng: PRNG,
+}
+impl OpeningBook{
+
+    pub fn new() -> Self{
+        OpeningBook{
+            entries: HashMap::new(),
+            rng: PRNG::new(DEFAULT_SEED),
+        }
+    }
+
+    /// Builds a book from the first max_plies plies of every game
+    ///
+    /// a move gets 2 points each time the side playing it went on to win,
+    /// 1 point for a draw and nothing for a loss
+    pub fn from_records(records: &[GameRecord], max_plies: usize) -> Self{
+
+        let mut result = Self::new();
+
+        for record in records{
+
+            // records are checked when they are read
+            let Ok(mut position) = Position::from_FEN(&record.start_fen) else{
+                continue;
+            };
+
+            for m in record.moves.iter().take(max_plies){
+
+                let weight = match (record.result, position.side_to_move()){
+                    (GameResult::Draw, _) => 1,
+                    (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => 2,
+                    _ => 0,
+                };
+
+                result.add(&position, *m, weight);
+                position.make_move(*m);
+            }
+        }
+
+        result
+    }
+
+    /// seeds the generator used by choose, 0 is replaced by the default seed
+    pub fn seed(&mut self, seed: u64){
+        self.rng = PRNG::new(if seed == 0 {DEFAULT_SEED} else {seed});
+    }
+
+    pub fn len(&self) -> usize{
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.entries.is_empty()
+    }
+
+    /// adds weight to m in position, adding the move if it is not in the book yet
+    pub fn add(&mut self, position: &Position, m: Move, weight: u32){
+
+        let moves = self.entries.entry(position.zobrist_hash()).or_default();
+        let packed = u32::from(m);
+
+        match moves.iter_mut().find(|(book_move, _)| *book_move == packed){
+            Some((_, w)) => *w = w.saturating_add(weight),
+            None => moves.push((packed, weight)),
+        }
+    }
+
+    /// Returns the book moves for position with their weights
+    ///
+    /// entries are matched against the generated moves, so hash
+    /// collisions and stale entries can't return a move that isn't psuedolegal
+    pub fn probe(&self, position: &Position) -> Vec<(Move, u32)>{
+
+        let Some(book_moves) = self.entries.get(&position.zobrist_hash()) else{
+            return Vec::new();
+        };
+
+        let generated = position.generate_moves();
+
+        book_moves.iter()
+            .filter(|(_, weight)| *weight != 0)
+            .filter_map(|(packed, weight)| {
+                generated.iter().find(|m| u32::from(**m) == *packed).map(|m| (*m, *weight))
+            })
+            .collect()
+    }
+
+    /// Picks one of the book moves for position, weighted at random
+    pub fn choose(&mut self, position: &Position) -> Option<Move>{
+
+        let moves = self.probe(position);
+        let total: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+
+        if total == 0{
+            return None;
+        }
+
+        let mut pick = self.rng.rand64() % total;
+
+        for (m, weight) in moves{
+            if pick < weight as u64{
+                return Some(m);
+            }
+            pick -= weight as u64;
+        }
+
+        unreachable!()
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()>{
+
+        let mut sorted: Vec<(u64, u32, u32)> = self.entries.iter()
+            .flat_map(|(hash, moves)| moves.iter().map(|(m, w)| (*hash, *m, *w)))
+            .collect();
+        sorted.sort_unstable();
+
+        writer.write_all(MAGIC)?;
+        for (hash, m, weight) in sorted{
+            writer.write_all(&hash.to_le_bytes())?;
+            writer.write_all(&m.to_le_bytes())?;
+            writer.write_all(&weight.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, ReadBookError>{
+
+        let mut bytes: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let entries = bytes.strip_prefix(MAGIC).ok_or(ReadBookError{})?;
+        if entries.len() % 16 != 0{
+            return Err(ReadBookError{});
+        }
+
+        let mut result = Self::new();
+
+        for entry in entries.chunks_exact(16){
+            let hash = u64::from_le_bytes(entry[0..8].try_into().unwrap());
+            let m = u32::from_le_bytes(entry[8..12].try_into().unwrap());
+            let weight = u32::from_le_bytes(entry[12..16].try_into().unwrap());
+
+            result.entries.entry(hash).or_default().push((m, weight));
+        }
+
+        Ok(result)
+    }
+}
+impl Default for OpeningBook{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    fn test_record(moves: &[&str], result: GameResult) -> GameRecord{
+
+        let mut position = Position::from_start_position();
+        let moves = moves.iter().map(|notation| {
+            let m = position.parse_move(notation).unwrap();
+            position.make_move(m);
+            m
+        }).collect();
+
+        GameRecord{
+            white: String::new(),
+            black: String::new(),
+            start_fen: Position::from_start_position().to_FEN(),
+            moves,
+            result,
+        }
+    }
+
+    #[test]
+    fn build_and_probe_test(){
+
+        let records = [
+            test_record(&["e2e4", "e7e5"], GameResult::WhiteWins),
+            test_record(&["e2e4", "d7d5"], GameResult::Draw),
+            test_record(&["d2d4", "d7d5"], GameResult::BlackWins),
+        ];
+
+        let book = OpeningBook::from_records(&records, 1);
+        let start = Position::from_start_position();
+
+        let mut moves = book.probe(&start);
+        moves.sort_by_key(|(m, _)| m.to_string());
+
+        // e2e4: win + draw, d2d4 lost so it is never played
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].0, start.parse_move("e2e4").unwrap());
+        assert_eq!(moves[0].1, 3);
+
+        // only the first ply was added
+        let mut after = start.clone();
+        after.make_move(start.parse_move("e2e4").unwrap());
+        assert!(book.probe(&after).is_empty());
+    }
+
+    #[test]
+    fn weighted_choice_test(){
+
+        let start = Position::from_start_position();
+        let e4 = start.parse_move("e2e4").unwrap();
+        let d4 = start.parse_move("d2d4").unwrap();
+
+        let mut book = OpeningBook::new();
+        book.add(&start, e4, 3);
+        book.add(&start, d4, 1);
+
+        book.seed(7);
+        let picks: Vec<Move> = (0..400).map(|_| book.choose(&start).unwrap()).collect();
+        let e4_count = picks.iter().filter(|m| **m == e4).count();
+
+        assert!(e4_count > 250 && e4_count < 350, "{e4_count}");
+
+        // same seed, same choices
+        book.seed(7);
+        let again: Vec<Move> = (0..400).map(|_| book.choose(&start).unwrap()).collect();
+        assert_eq!(picks, again);
+    }
+
+    #[test]
+    fn book_file_test(){
+
+        let start = Position::from_start_position();
+        let mut book = OpeningBook::new();
+        book.add(&start, start.parse_move("e2e4").unwrap(), 5);
+        book.add(&start, start.parse_move("c2c4").unwrap(), 2);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        book.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 2*16);
+
+        let read = OpeningBook::read_from(&mut bytes.as_slice()).unwrap();
+        let mut expected = book.probe(&start);
+        let mut observed = read.probe(&start);
+        expected.sort_by_key(|(m, _)| u32::from(*m));
+        observed.sort_by_key(|(m, _)| u32::from(*m));
+        assert_eq!(expected, observed);
+
+        assert!(OpeningBook::read_from(&mut &bytes[..20]).is_err());
+        assert!(OpeningBook::read_from(&mut &b"not a book"[..]).is_err());
+    }
+}
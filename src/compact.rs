@@ -0,0 +1,277 @@
+// replacements for the lookups in tables.rs that need only a few
+// kilobytes of tables, for targets where memory matters more than speed
+//
+// sliding moves walk one ray per direction, everything else is worked
+// out from the rank and file of the squares involved
+
+use crate::tables::*;
+use crate::types::{Bitboard, Square};
+
+// [direction][square], every square from square to the edge in that direction
+static RAYS: [[Bitboard; 64]; 8] = {
+    let mut result: [[Bitboard; 64]; 8] = [[Bitboard::EMPTY; 64]; 8];
+
+    let mut i = 0;
+    while i < 8{
+
+        let mut j = 0;
+        while j < 64{
+
+            let mut k = 1;
+            while k <= NUM_SQUARES_TO_EDGE[j][i]{
+                result[i][j].0 |= 1 << (j as i8 + DIR_OFFSETS[i]*k);
+                k += 1;
+            }
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    result
+};
+
+// moves along the given directions up to and including the first blocker
+#[inline]
+fn ray_lookup(square: Square, total_board: Bitboard, directions: [usize; 4]) -> Bitboard{
+    let mut result = Bitboard::EMPTY;
+
+    for direction in directions{
+        let ray = RAYS[direction][square];
+        let blockers = (ray & total_board).0;
+
+        if blockers == 0{
+            result |= ray;
+        }
+        else{
+            // the closest blocker is the lowest square on rays going up the board
+            let blocker = if DIR_OFFSETS[direction] > 0{
+                blockers.trailing_zeros()
+            }
+            else{
+                63 - blockers.leading_zeros()
+            };
+            result |= ray ^ RAYS[direction][blocker as usize];
+        }
+    }
+
+    result
+}
+
+// (rank, file) of a square index
+#[inline]
+fn coords(square: usize) -> (i8, i8){
+    ((square / 8) as i8, (square % 8) as i8)
+}
+
+#[inline]
+fn on_board(rank: i8, file: i8) -> bool{
+    (0..8).contains(&rank) && (0..8).contains(&file)
+}
+
+// the one square step from start towards to and the number of steps,
+// if they share a rank, file or diagonal
+#[inline]
+fn line_between(start: Square, to: Square) -> Option<((i8, i8), i8)>{
+    let (start_rank, start_file) = coords(start as usize);
+    let (to_rank, to_file) = coords(to as usize);
+
+    let (dr, df) = (to_rank - start_rank, to_file - start_file);
+
+    if (dr == 0 && df == 0) || (dr != 0 && df != 0 && dr.abs() != df.abs()){
+        return None;
+    }
+
+    Some(((dr.signum(), df.signum()), dr.abs().max(df.abs())))
+}
+
+#[inline]
+pub fn get_orth_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    ray_lookup(square, total_board, [0, 1, 2, 3])
+}
+
+#[inline]
+pub fn get_diag_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    ray_lookup(square, total_board, [4, 5, 6, 7])
+}
+
+#[inline]
+pub fn get_potential_stradler_captures(square: Square, friendly_stradlers: Bitboard) -> [Bitboard; 4]{
+    generate_stradler_captures(square, friendly_stradlers)
+}
+
+#[inline]
+pub fn get_death_squares(s1: Square, s2: Square) -> [Bitboard; 2]{
+    let (rank1, file1) = coords(s1 as usize);
+    let (rank2, file2) = coords(s2 as usize);
+
+    if rank1 == rank2 || file1 == file2{
+        return [Bitboard::EMPTY; 2];
+    }
+
+    let death1 = (rank1*8 + file2) as usize;
+    let death2 = (rank2*8 + file1) as usize;
+
+    // lowest square first, as in the table
+    [Bitboard(1 << death1.min(death2)), Bitboard(1 << death1.max(death2))]
+}
+
+#[inline]
+pub fn get_springer_landing_square(start: Square, capturing: Square) -> Bitboard{
+    let Some(((dr, df), _)) = line_between(start, capturing) else{
+        return Bitboard::EMPTY;
+    };
+
+    let (rank, file) = coords(capturing as usize);
+    if on_board(rank + dr, file + df){
+        Bitboard(1 << ((rank + dr)*8 + file + df))
+    }
+    else{
+        Bitboard::EMPTY
+    }
+}
+
+#[inline]
+pub fn get_springer_captured_square(start: Square, landing: Square) -> Bitboard{
+    match line_between(start, landing){
+        Some(((dr, df), distance)) if distance >= 2 => {
+            let (rank, file) = coords(landing as usize);
+            Bitboard(1 << ((rank - dr)*8 + file - df))
+        },
+        _ => Bitboard::EMPTY,
+    }
+}
+
+#[inline]
+pub fn get_retractor_lookup(start: Square, moving_to: Square) -> Bitboard{
+    match line_between(start, moving_to){
+        Some(((dr, df), 1)) => {
+            let (rank, file) = coords(start as usize);
+            if on_board(rank - dr, file - df){
+                Bitboard(1 << ((rank - dr)*8 + file - df))
+            }
+            else{
+                Bitboard::EMPTY
+            }
+        },
+        _ => Bitboard::EMPTY,
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+    use crate::utils::PRNG;
+
+    #[test]
+    fn sliding_test(){
+
+        let mut rng = PRNG::new(3262394871);
+
+        for square in Square::ALL{
+
+            // every relevant blocker subset, then some full boards
+            for (mask, lookup, generate) in [
+                (ORTH_RELEVANT_BLOCKERS[square], get_orth_moves as fn(Square, Bitboard) -> Bitboard, generate_orthogonal_lookup as fn(Square, Bitboard) -> Bitboard),
+                (DIAG_RELEVANT_BLOCKERS[square], get_diag_moves, generate_diagonal_lookup),
+            ]{
+                let mut subset = Bitboard::EMPTY;
+                loop{
+                    assert_eq!(lookup(square, subset), generate(square, subset));
+
+                    subset.0 = subset.0.wrapping_sub(mask.0) & mask.0;
+                    if subset.is_empty(){
+                        break;
+                    }
+                }
+
+                for _ in 0..100{
+                    let board = Bitboard(rng.rand64() & rng.rand64());
+                    assert_eq!(lookup(square, board), generate(square, board));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn springer_test(){
+
+        // [start][landing] -> captured, worked out the same way as the table
+        let mut captured_lookup = [[Bitboard::EMPTY; 64]; 64];
+
+        for start in Square::ALL{
+            for captured in Square::ALL{
+                let landing = generate_springer_captures(start, Bitboard::from(captured));
+                assert_eq!(get_springer_landing_square(start, captured), landing);
+
+                if !landing.is_empty(){
+                    captured_lookup[start][landing.bitscanforward()] = Bitboard::from(captured);
+                }
+            }
+        }
+
+        for start in Square::ALL{
+            for landing in Square::ALL{
+                assert_eq!(get_springer_captured_square(start, landing), captured_lookup[start][landing]);
+            }
+        }
+    }
+
+    // the tables only exist without compact-tables, every accessor is
+    // checked against them over its whole domain
+
+    // every subset of mask, with and without the squares outside it set
+    #[cfg(not(feature = "compact-tables"))]
+    fn boards(mask: Bitboard) -> Vec<Bitboard>{
+        let mut result = Vec::new();
+        let mut subset = Bitboard::EMPTY;
+        loop{
+            result.push(subset);
+            result.push(subset | !mask);
+
+            subset.0 = subset.0.wrapping_sub(mask.0) & mask.0;
+            if subset.is_empty(){
+                break;
+            }
+        }
+        result
+    }
+
+    #[cfg(not(feature = "compact-tables"))]
+    #[test]
+    fn sliding_table_test(){
+        for square in Square::ALL{
+            for board in boards(ORTH_RELEVANT_BLOCKERS[square]){
+                assert_eq!(get_orth_moves(square, board), crate::tables::get_orth_moves(square, board));
+            }
+            for board in boards(DIAG_RELEVANT_BLOCKERS[square]){
+                assert_eq!(get_diag_moves(square, board), crate::tables::get_diag_moves(square, board));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "compact-tables"))]
+    #[test]
+    fn stradler_table_test(){
+        for square in Square::ALL{
+            for board in boards(RELEVANT_BUDDIES[square]){
+                assert_eq!(get_potential_stradler_captures(square, board), crate::tables::get_potential_stradler_captures(square, board));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "compact-tables"))]
+    #[test]
+    fn square_pair_table_test(){
+        for s1 in Square::ALL{
+            for s2 in Square::ALL{
+                assert_eq!(get_death_squares(s1, s2), crate::tables::get_death_squares(s1, s2));
+                assert_eq!(get_springer_landing_square(s1, s2), crate::tables::get_springer_landing_square(s1, s2));
+                assert_eq!(get_springer_captured_square(s1, s2), crate::tables::get_springer_captured_square(s1, s2));
+                assert_eq!(get_retractor_lookup(s1, s2), crate::tables::get_retractor_lookup(s1, s2));
+            }
+        }
+    }
+}
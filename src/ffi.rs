@@ -0,0 +1,343 @@
+// C interface to the move generator, behind the ffi feature. the
+// shared library is built with `cargo build --release --features ffi`
+//
+// positions are handed out as opaque pointers that own an undo stack,
+// so moves are taken back without passing them in again. moves are the
+// u32 encoding of Move and squares are 0 (A1) to 63 (H8). every pointer
+// passed in has to come from these functions or be a valid C string or
+// buffer, NULL is accepted and ignored where noted
+
+use crate::position::{Position, Undo};
+use crate::types::{Color, Piece, Square};
+use crate::r#move::{Move, MoveSink};
+
+use std::ffi::{c_char, c_int, CStr};
+use std::ops::ControlFlow;
+use std::ptr;
+
+/// bumped whenever a declaration in the header changes
+const API_VERSION: u32 = 1;
+
+pub struct HmgPosition{
+    position: Position,
+    undos: Vec<Undo>,
+}
+
+// the C spelling of the types used by the functions below
+trait CType{
+    const C: &'static str;
+}
+macro_rules! c_types{
+    ($($ty:ty => $c:literal),* $(,)?) => {
+        $(impl CType for $ty{ const C: &'static str = $c; })*
+    };
+}
+c_types!{
+    () => "void",
+    u8 => "uint8_t",
+    u32 => "uint32_t",
+    usize => "size_t",
+    c_int => "int",
+    c_char => "char",
+    *mut u32 => "uint32_t *",
+    *mut c_char => "char *",
+    *const c_char => "const char *",
+    *mut HmgPosition => "HmgPosition *",
+    *const HmgPosition => "const HmgPosition *",
+}
+
+// "type name" with the pointer star next to the name, as C writes it
+fn c_declarator(ty: &str, name: &str) -> String{
+    if ty.ends_with('*') {format!("{ty}{name}")} else {format!("{ty} {name}")}
+}
+
+// the C declaration of a function, from its parameter names and types.
+// it doesn't compile unless the function has exactly that signature
+macro_rules! declaration{
+    ($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {{
+        let _: unsafe extern "C" fn($($ty),*) -> $ret = $name;
+
+        let parameters: &[String] = &[$(c_declarator(<$ty as CType>::C, stringify!($arg))),*];
+        let parameters = if parameters.is_empty() {"void".to_string()} else {parameters.join(", ")};
+
+        format!("{}({parameters})", c_declarator(<$ret as CType>::C, stringify!($name)))
+    }};
+}
+
+// (comment, declaration) of every function below, in the order they
+// appear in the header
+fn declarations() -> [(&'static str, String); 11]{[
+    ("version of this interface, HMG_API_VERSION of the header it was built with",
+        declaration!(hmg_api_version() -> u32)),
+    ("the starting position, free with hmg_position_free",
+        declaration!(hmg_position_start() -> *mut HmgPosition)),
+    ("the position described by fen, NULL if it can't be read or isn't valid.\n   free with hmg_position_free",
+        declaration!(hmg_position_from_fen(fen: *const c_char) -> *mut HmgPosition)),
+    ("frees a position, NULL is ignored",
+        declaration!(hmg_position_free(position: *mut HmgPosition) -> ())),
+    ("writes up to capacity legal moves into moves and returns the number of\n   legal moves, which is more than capacity if some didn't fit",
+        declaration!(hmg_legal_moves(position: *mut HmgPosition, moves: *mut u32, capacity: usize) -> usize)),
+    ("plays move, 0 on success and -1 if it isn't legal in the position",
+        declaration!(hmg_make_move(position: *mut HmgPosition, move: u32) -> c_int)),
+    ("takes back the last move played with hmg_make_move, 0 on success and\n   -1 if there is none",
+        declaration!(hmg_unmake_move(position: *mut HmgPosition) -> c_int)),
+    ("FEN letter of the piece on square, uppercase for white, '.' if it is\n   empty and 0 if square is off the board",
+        declaration!(hmg_piece_at(position: *const HmgPosition, square: u8) -> c_char)),
+    ("writes the position as FEN into buffer, cut short to fit capacity with\n   the terminating 0, and returns the length of the whole FEN like snprintf",
+        declaration!(hmg_to_fen(position: *const HmgPosition, buffer: *mut c_char, capacity: usize) -> usize)),
+    ("square move starts from, 64 if it isn't a move",
+        declaration!(hmg_move_from(move: u32) -> u8)),
+    ("square move ends on, 64 if it isn't a move",
+        declaration!(hmg_move_to(move: u32) -> u8)),
+]}
+
+/// The C header declaring the functions exported by the cdylib,
+/// checked in as include/hmg.h. the declarations are checked against
+/// the functions when this compiles
+pub fn c_header() -> String{
+
+    let mut result = String::from("\
+/* C interface to the hmg move generator, generated by `hmg header`.
+   squares are 0 (A1) to 63 (H8) and moves are the u32 encoding used by
+   the crate, they are only meaningful for the position they came from */
+
+#ifndef HMG_H
+#define HMG_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+
+");
+
+    result.push_str(&format!("#define HMG_API_VERSION {API_VERSION}\n\n"));
+    result.push_str("typedef struct HmgPosition HmgPosition;\n");
+
+    for (comment, declaration) in declarations(){
+        result.push_str(&format!("\n/* {comment} */\n{declaration};\n"));
+    }
+
+    result.push_str("
+#ifdef __cplusplus
+}
+#endif
+
+#endif
+");
+
+    result
+}
+
+#[no_mangle]
+pub extern "C" fn hmg_api_version() -> u32{
+    API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn hmg_position_start() -> *mut HmgPosition{
+    Box::into_raw(Box::new(HmgPosition{ position: Position::from_start_position(), undos: Vec::new() }))
+}
+
+/// # Safety
+/// fen has to be NULL or a valid C string
+#[no_mangle]
+pub unsafe extern "C" fn hmg_position_from_fen(fen: *const c_char) -> *mut HmgPosition{
+
+    if fen.is_null(){
+        return ptr::null_mut();
+    }
+
+    let Ok(fen) = CStr::from_ptr(fen).to_str() else{
+        return ptr::null_mut();
+    };
+
+    // move generation can only be trusted on valid positions
+    match Position::from_FEN(fen){
+        Ok(position) if position.validate().is_ok() => Box::into_raw(Box::new(HmgPosition{ position, undos: Vec::new() })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// position has to be NULL or come from hmg_position_start or
+/// hmg_position_from_fen, and not be used again
+#[no_mangle]
+pub unsafe extern "C" fn hmg_position_free(position: *mut HmgPosition){
+    if !position.is_null(){
+        drop(Box::from_raw(position));
+    }
+}
+
+/// # Safety
+/// position has to be a live position and moves has to have room for
+/// capacity u32s, it may be NULL if capacity is 0
+#[no_mangle]
+pub unsafe extern "C" fn hmg_legal_moves(position: *mut HmgPosition, moves: *mut u32, capacity: usize) -> usize{
+
+    let position = &mut (*position).position;
+
+    // writes the moves that fit and counts them all
+    struct Buffer{
+        moves: *mut u32,
+        capacity: usize,
+        count: usize,
+    }
+    impl MoveSink for Buffer{
+        fn push(&mut self, m: Move) -> ControlFlow<()>{
+            if self.count < self.capacity{
+                // SAFETY: the caller gives room for capacity u32s
+                unsafe{ *self.moves.add(self.count) = u32::from(m); }
+            }
+            self.count += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    let mut buffer = Buffer{ moves, capacity, count: 0 };
+    position.generate_legal_moves_into(&mut buffer);
+    buffer.count
+}
+
+/// # Safety
+/// position has to be a live position
+#[no_mangle]
+pub unsafe extern "C" fn hmg_make_move(position: *mut HmgPosition, m: u32) -> c_int{
+
+    let position = &mut *position;
+
+    let Ok(m) = Move::try_from(m) else{
+        return -1;
+    };
+
+    match position.position.try_make_move(m){
+        Ok(undo) => {
+            position.undos.push(undo);
+            0
+        },
+        Err(_) => -1,
+    }
+}
+
+/// # Safety
+/// position has to be a live position
+#[no_mangle]
+pub unsafe extern "C" fn hmg_unmake_move(position: *mut HmgPosition) -> c_int{
+
+    let position = &mut *position;
+
+    let Some(undo) = position.undos.last() else{
+        return -1;
+    };
+
+    match position.position.undo_move(*undo){
+        Ok(()) => {
+            position.undos.pop();
+            0
+        },
+        Err(_) => -1,
+    }
+}
+
+/// # Safety
+/// position has to be a live position
+#[no_mangle]
+pub unsafe extern "C" fn hmg_piece_at(position: *const HmgPosition, square: u8) -> c_char{
+
+    let Ok(square) = Square::try_from(square as usize) else{
+        return 0;
+    };
+
+    let position = &(*position).position;
+
+    for color in [Color::White, Color::Black]{
+        for piece in &Piece::ALL[1..]{
+            if position.pieces(color, *piece).contains(square){
+                return Piece::PIECE_SYMBOLS[color | *piece] as c_char;
+            }
+        }
+    }
+
+    b'.' as c_char
+}
+
+/// # Safety
+/// position has to be a live position and buffer has to have room for
+/// capacity chars, it may be NULL if capacity is 0
+#[no_mangle]
+pub unsafe extern "C" fn hmg_to_fen(position: *const HmgPosition, buffer: *mut c_char, capacity: usize) -> usize{
+
+    let fen = (*position).position.to_FEN();
+
+    if capacity > 0{
+        let written = fen.len().min(capacity - 1);
+        ptr::copy_nonoverlapping(fen.as_ptr().cast::<c_char>(), buffer, written);
+        *buffer.add(written) = 0;
+    }
+
+    fen.len()
+}
+
+#[no_mangle]
+pub extern "C" fn hmg_move_from(m: u32) -> u8{
+    Move::try_from(m).map_or(64, |m| m.get_from() as u8)
+}
+
+#[no_mangle]
+pub extern "C" fn hmg_move_to(m: u32) -> u8{
+    Move::try_from(m).map_or(64, |m| m.get_to() as u8)
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    #[test]
+    fn header_test(){
+        // regenerate with `hmg header > include/hmg.h`
+        let header = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/include/hmg.h")).unwrap();
+        assert_eq!(header, c_header());
+    }
+
+    #[test]
+    fn ffi_test(){
+
+        unsafe{
+            assert!(hmg_position_from_fen(ptr::null()).is_null());
+            assert!(hmg_position_from_fen(c"8/8 w 0 1".as_ptr()).is_null());
+            // two white kings
+            assert!(hmg_position_from_fen(c"k7/8/8/8/8/8/8/KK6 w 0 1".as_ptr()).is_null());
+
+            let position = hmg_position_from_fen(c"k7/8/8/8/3n4/8/3B4/K7 w 0 1".as_ptr());
+            assert!(!position.is_null());
+
+            let count = hmg_legal_moves(position, ptr::null_mut(), 0);
+            let mut moves = vec![0; count];
+            assert_eq!(hmg_legal_moves(position, moves.as_mut_ptr(), count), count);
+
+            assert_eq!(hmg_piece_at(position, Square::D4 as u8), b'n' as c_char);
+            assert_eq!(hmg_piece_at(position, 64), 0);
+            assert_eq!(hmg_move_from(0), 64);
+
+            let leap = *moves.iter().find(|m| hmg_move_from(**m) == Square::D2 as u8 && hmg_move_to(**m) == Square::D5 as u8).unwrap();
+            assert_eq!(hmg_make_move(position, leap), 0);
+            assert_eq!(hmg_make_move(position, leap), -1);
+            assert_eq!(hmg_piece_at(position, Square::D4 as u8), b'.' as c_char);
+
+            let mut buffer = [0 as c_char; 8];
+            let length = hmg_to_fen(position, buffer.as_mut_ptr(), buffer.len());
+            assert_eq!(length, (*position).position.to_FEN().len());
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "k7/8/8/");
+
+            assert_eq!(hmg_unmake_move(position), 0);
+            assert_eq!(hmg_unmake_move(position), -1);
+            assert_eq!(hmg_piece_at(position, Square::D4 as u8), b'n' as c_char);
+
+            hmg_position_free(position);
+            hmg_position_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,10 +1,39 @@
 mod tables;
 mod position;
+mod search;
+mod record;
+mod selfplay;
+mod book;
+mod zobrist;
+mod tablebase;
+mod mate;
+mod magic;
+mod rules;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(all(feature = "pext", not(feature = "compact-tables")))]
+mod pext;
+// built for tests too, so it can be checked against the full tables
+#[cfg(any(test, feature = "compact-tables"))]
+mod compact;
+#[cfg(feature = "serde")]
+mod serialize;
 pub(crate) mod r#move;
 pub(crate) mod utils;
 pub(crate) mod types;
 
-pub use position::Position;
-pub use r#move::{Move, MoveList};
+pub use position::{Position, ReadFenError, ReadBytesError, IllegalMove, Undo, PositionIssue, ThreatMap, POSITION_BYTES, BYTES_VERSION};
+pub use r#move::{Move, MoveList, MoveSink, MoveCount, FirstMove, GenFilter};
+pub use search::{Engine, EngineConfig, EvalWeights, SearchResult, ParseConfigError, evaluate, MATE_SCORE};
+pub use record::{GameRecord, GameResult, ReadRecordError};
+pub use book::{OpeningBook, ReadBookError};
+pub use tablebase::{Material, Tablebase, TablebaseSet, TablebaseValue, ParseMaterialError, ReadTablebaseError, MAX_PIECES};
+pub use mate::{MateLine, Defence, MateSolution, solve_mate};
+pub use magic::{Magic, MagicTable, ParseMagicTableError, table_size, verify_tables};
+pub use rules::{Rules, ParseRulesError};
+#[cfg(feature = "ffi")]
+pub use ffi::c_header;
+pub use selfplay::{MatchConfig, MatchError, MatchResult, play_game, play_match, read_openings};
 
+pub use types::{Bitboard, Color, Piece, Squares, Square, LowercaseSquare, ParseSquareError, ConversionError};
 pub use tables::*;
@@ -0,0 +1,280 @@
+use crate::tables::*;
+use crate::types::{Bitboard, Square};
+use crate::utils::{num_and_all, PRNG};
+
+use std::fmt::{self, Write};
+use std::error;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct ParseMagicTableError{}
+impl fmt::Display for ParseMagicTableError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading magic table name")
+    }
+}
+impl error::Error for ParseMagicTableError{}
+
+/// A magic number and the shift used with it in tables::magic_index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Magic{
+    pub magic: u64,
+    pub throwaway: u8,
+}
+impl Magic{
+    /// number of bits the index into the lookup table has
+    pub fn index_bits(&self) -> u32{
+        64 - self.throwaway as u32
+    }
+
+    /// number of lookup table entries the square needs
+    pub fn entries(&self) -> usize{
+        1 << self.index_bits()
+    }
+}
+
+num_and_all!{
+/// The lookup tables in tables.rs that are indexed with magics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagicTable{
+    Orth,
+    Diag,
+    Stradler,
+}
+}
+impl FromStr for MagicTable{
+    type Err = ParseMagicTableError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        match s.to_ascii_lowercase().as_str(){
+            "orth" => Ok(Self::Orth),
+            "diag" => Ok(Self::Diag),
+            "stradler" => Ok(Self::Stradler),
+            _ => Err(ParseMagicTableError{}),
+        }
+    }
+}
+impl MagicTable{
+
+    /// squares whose occupancy decides the lookup for square
+    pub fn mask(self, square: Square) -> Bitboard{
+        match self{
+            Self::Orth => ORTH_RELEVANT_BLOCKERS[square],
+            Self::Diag => DIAG_RELEVANT_BLOCKERS[square],
+            Self::Stradler => RELEVANT_BUDDIES[square],
+        }
+    }
+
+    /// the magic currently in tables.rs for square
+    pub fn current(self, square: Square) -> Magic{
+        match self{
+            Self::Orth => Magic{ magic: ORTH_MAGICS[square], throwaway: ORTH_THROWAWAY[square] },
+            Self::Diag => Magic{ magic: DIAG_MAGICS[square], throwaway: DIAG_THROWAWAY[square] },
+            Self::Stradler => Magic{ magic: STRADLER_MAGICS[square], throwaway: STRADLER_THROWAWAY[square] },
+        }
+    }
+
+    /// the magics currently in tables.rs for every square
+    pub fn current_magics(self) -> [Magic; 64]{
+        Square::ALL.map(|square| self.current(square))
+    }
+
+    /// what has to be stored at the index of subset, subsets may only
+    /// share an index if their entries are the same
+    fn entry(self, square: Square, subset: Bitboard) -> [Bitboard; 4]{
+        match self{
+            Self::Orth => [generate_orthogonal_lookup(square, subset), Bitboard::EMPTY, Bitboard::EMPTY, Bitboard::EMPTY],
+            Self::Diag => [generate_diagonal_lookup(square, subset), Bitboard::EMPTY, Bitboard::EMPTY, Bitboard::EMPTY],
+            Self::Stradler => generate_stradler_captures(square, subset),
+        }
+    }
+
+    // name used for the statics in tables.rs
+    fn prefix(self) -> &'static str{
+        match self{
+            Self::Orth => "ORTH",
+            Self::Diag => "DIAG",
+            Self::Stradler => "STRADLER",
+        }
+    }
+
+    /// true if no two blocker subsets with different entries share an index
+    pub fn verify(self, square: Square, magic: Magic) -> bool{
+        self.fill(square, magic, &mut Vec::new())
+    }
+
+    // fills used with the entries for every subset of the mask, stopping at the first collision
+    fn fill(self, square: Square, magic: Magic, used: &mut Vec<[Bitboard; 4]>) -> bool{
+
+        let mask = self.mask(square);
+
+        // fewer entries than subsets is fine as long as the collisions are
+        // constructive, more than 2^32 is never what anyone meant
+        if magic.index_bits() > 32{
+            return false;
+        }
+
+        used.clear();
+        used.resize(magic.entries(), [Bitboard::UNUSED; 4]);
+
+        let mut subset = Bitboard::EMPTY;
+        loop{
+            let index = magic_index(subset, magic.magic, magic.throwaway);
+            let entry = self.entry(square, subset);
+
+            if used[index][0].is_unused(){
+                used[index] = entry;
+            }
+            else if used[index] != entry{
+                return false;
+            }
+
+            subset.0 = subset.0.wrapping_sub(mask.0) & mask.0;
+            if subset.is_empty(){
+                return true;
+            }
+        }
+    }
+
+    /// Searches for a magic for every square, trying to make each index
+    /// one bit shorter than the best magic known so far
+    ///
+    /// the current magics are the starting point, so the result is never
+    /// larger than the table in tables.rs. tries is the number of random
+    /// candidates tested for each index size before giving up on it
+    pub fn find_magics(self, seed: u64, tries: u32) -> [Magic; 64]{
+
+        let mut rng = PRNG::new(seed);
+        let mut used = Vec::new();
+
+        Square::ALL.map(|square| {
+
+            let mut best = self.current(square);
+
+            if !self.fill(square, best, &mut used){
+                // every square has a magic with one bit per mask square,
+                // so keep going until it turns up
+                let throwaway = 64 - self.mask(square).0.count_ones() as u8;
+                best = loop{
+                    if let Some(magic) = self.try_magics(square, throwaway, &mut rng, tries, &mut used){
+                        break magic;
+                    }
+                };
+            }
+
+            while best.index_bits() > 1{
+                match self.try_magics(square, best.throwaway+1, &mut rng, tries, &mut used){
+                    Some(magic) => best = magic,
+                    None => break,
+                }
+            }
+
+            best
+        })
+    }
+
+    fn try_magics(self, square: Square, throwaway: u8, rng: &mut PRNG, tries: u32, used: &mut Vec<[Bitboard; 4]>) -> Option<Magic>{
+
+        let mask = self.mask(square);
+
+        for _ in 0..tries{
+            // sparse candidates work best
+            let magic = rng.rand64() & rng.rand64() & rng.rand64();
+
+            // a magic has to move enough of the mask into the top bits to have a chance
+            if (mask.0.wrapping_mul(magic) >> 56).count_ones() < 6 && mask.0.count_ones() >= 6{
+                continue;
+            }
+
+            let candidate = Magic{ magic, throwaway };
+            if self.fill(square, candidate, used){
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    /// Rust source for the magics, throwaways and lookup table size of
+    /// this table, written to replace the ones in tables.rs
+    pub fn write_source(self, magics: &[Magic; 64]) -> String{
+
+        let prefix = self.prefix();
+        let mut source = String::new();
+
+        writeln!(source, "pub static {prefix}_MAGICS: [u64; 64] = [").unwrap();
+        for row in magics.chunks(4){
+            let row: Vec<String> = row.iter().map(|m| format!("{:#x}_u64", m.magic)).collect();
+            writeln!(source, "    {},", row.join(", ")).unwrap();
+        }
+        writeln!(source, "];").unwrap();
+
+        writeln!(source, "pub static {prefix}_THROWAWAY: [u8; 64] = [").unwrap();
+        for row in magics.chunks(8){
+            let row: Vec<String> = row.iter().map(|m| m.throwaway.to_string()).collect();
+            writeln!(source, "    {},", row.join(", ")).unwrap();
+        }
+        writeln!(source, "];").unwrap();
+
+        writeln!(source, "pub const {prefix}_LOOKUP_SIZE: usize = {};", table_size(magics)).unwrap();
+
+        source
+    }
+}
+
+/// total lookup table entries needed by magics
+pub fn table_size(magics: &[Magic; 64]) -> usize{
+    magics.iter().map(Magic::entries).sum()
+}
+
+/// Every table and square whose magic in tables.rs has a destructive collision
+pub fn verify_tables() -> Vec<(MagicTable, Square)>{
+    MagicTable::ALL.into_iter()
+        .flat_map(|table| Square::ALL.into_iter().map(move |square| (table, square)))
+        .filter(|(table, square)| !table.verify(*square, table.current(*square)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    #[test]
+    fn current_magics_test(){
+        assert_eq!(verify_tables(), []);
+
+        assert_eq!(table_size(&MagicTable::Orth.current_magics()), ORTH_LOOKUP_SIZE);
+        assert_eq!(table_size(&MagicTable::Stradler.current_magics()), STRADLER_LOOKUP_SIZE);
+
+        // the retractor lookups are indexed directly, not through magics
+        assert_eq!(MagicTable::ALL, [MagicTable::Orth, MagicTable::Diag, MagicTable::Stradler]);
+        assert!("retractor".parse::<MagicTable>().is_err());
+
+        // a magic that sends everything to index 0
+        assert!(!MagicTable::Diag.verify(Square::D4, Magic{ magic: 0, throwaway: 55 }));
+    }
+
+    #[test]
+    fn find_magics_test(){
+
+        let magics = MagicTable::Stradler.find_magics(3262394871, 1000);
+
+        for square in Square::ALL{
+            assert!(MagicTable::Stradler.verify(square, magics[square]));
+            assert!(magics[square].throwaway >= STRADLER_THROWAWAY[square]);
+        }
+
+        assert!(table_size(&magics) <= STRADLER_LOOKUP_SIZE);
+    }
+
+    #[test]
+    fn write_source_test(){
+
+        let source = MagicTable::Diag.write_source(&MagicTable::Diag.current_magics());
+
+        assert!(source.starts_with("pub static DIAG_MAGICS: [u64; 64] = [\n    0x8400414004202a0_u64, 0x8a52540c04034010_u64,"));
+        assert!(source.contains("pub static DIAG_THROWAWAY: [u8; 64] = [\n    58, 59, 59, 59, 59, 59, 59, 58,\n"));
+        assert!(source.ends_with("pub const DIAG_LOOKUP_SIZE: usize = 5248;\n"));
+    }
+}
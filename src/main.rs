@@ -1,9 +1,310 @@
 use std::io::{self, Write};
-use hmg::{Position, Move, MoveList};
+use std::fs::{self, File};
+use std::env;
+use std::error::Error;
+use std::process::ExitCode;
+use hmg::{Position, Move, MoveList, Engine, EngineConfig, MatchConfig, MatchResult, GameRecord, OpeningBook, Material, TablebaseSet, TablebaseValue, MagicTable};
 
 const MOVES_PER_ROW: usize = 15;
 
-fn main() -> io::Result<()>{
+const USAGE: &str = "\
+usage:
+    hmg                     play moves interactively
+    hmg match [options]     play two engine configurations against each other
+    hmg book [options] <record files...>
+                            build an opening book from game records
+    hmg tablebase [options] <materials...>
+                            generate endgame tablebases, e.g. KUvK KRvKP
+    hmg mate <n> <fen>      find every way for the side to play to mate in n
+    hmg magics [options]    search for smaller magics and print them as Rust source
+    hmg header              print the C header for the cdylib, see include/hmg.h
+                            (built with the ffi feature)
+
+match options:
+    --engine1 <config>      e.g. name=a,depth=3,nodes=100000,mobility=2
+    --engine2 <config>      (material weights: stradler, coordinator, springer,
+                            chameleon, retractor, immobilizer)
+    --games <n>             number of games, default 2
+    --openings <file>       file with one FEN per line, default start position
+    --max-plies <n>         adjudicate as a draw after n plies, default 200
+    --out <file>            write every game to a record file
+    --book <file>           opening book used by both engines
+    --tablebases <file>     tablebases probed by both engines
+
+book options:
+    --out <file>            where to write the book (required)
+    --max-plies <n>         plies of each game added to the book, default 16
+
+tablebase options:
+    --out <file>            where to write the tablebases (required)
+
+magics options:
+    --table <name>          orth, diag or stradler, default all of them
+    --seed <n>              seed for the random candidates, default 3262394871
+    --tries <n>             candidates per square and index size, default 100000
+    --verify                only check the magics in tables.rs";
+
+fn main() -> ExitCode{
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result: Result<(), Box<dyn Error>> = match args.first().map(String::as_str){
+        None => interactive().map_err(Into::into),
+        Some("match") => run_match(&args[1..]),
+        Some("book") => run_book(&args[1..]),
+        Some("tablebase") => run_tablebase(&args[1..]),
+        Some("mate") => run_mate(&args[1..]),
+        Some("magics") => run_magics(&args[1..]),
+        #[cfg(feature = "ffi")]
+        Some("header") => {
+            print!("{}", hmg::c_header());
+            Ok(())
+        },
+        Some(_) => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(e) = result{
+        eprintln!("error: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// parses an engine config, naming it default_name unless the config has a name
+fn parse_engine(spec: &str, default_name: &str) -> Result<EngineConfig, hmg::ParseConfigError>{
+    let mut config: EngineConfig = spec.parse()?;
+
+    if !spec.split(',').any(|option| option.trim().starts_with("name=")){
+        config.name = default_name.to_string();
+    }
+
+    Ok(config)
+}
+
+fn run_match(args: &[String]) -> Result<(), Box<dyn Error>>{
+
+    let mut first = EngineConfig{name: String::from("engine1"), ..EngineConfig::default()};
+    let mut second = EngineConfig{name: String::from("engine2"), ..EngineConfig::default()};
+    let mut config = MatchConfig::default();
+    let mut out: Option<File> = None;
+    let mut book: Option<String> = None;
+    let mut tablebases: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next(){
+
+        let value = args.next().ok_or(format!("missing value for {flag}\n{USAGE}"))?;
+
+        match flag.as_str(){
+            "--engine1" => first = parse_engine(value, "engine1")?,
+            "--engine2" => second = parse_engine(value, "engine2")?,
+            "--games" => config.games = value.parse()?,
+            "--max-plies" => config.max_plies = value.parse()?,
+            "--openings" => config.openings = hmg::read_openings(&fs::read_to_string(value)?)?,
+            "--out" => out = Some(File::create(value)?),
+            "--book" => book = Some(value.clone()),
+            "--tablebases" => tablebases = Some(value.clone()),
+            _ => return Err(format!("unknown option {flag}\n{USAGE}").into()),
+        }
+    }
+
+    let mut first = Engine::new(first);
+    let mut second = Engine::new(second);
+
+    if let Some(book) = book{
+        first.set_book(OpeningBook::read_from(&mut File::open(&book)?)?);
+        second.set_book(OpeningBook::read_from(&mut File::open(&book)?)?);
+    }
+
+    if let Some(tablebases) = tablebases{
+        first.set_tablebases(TablebaseSet::read_from(&mut File::open(&tablebases)?)?);
+        second.set_tablebases(TablebaseSet::read_from(&mut File::open(&tablebases)?)?);
+    }
+
+    println!("{} vs {}, {} games", first.config().name, second.config().name, config.games);
+
+    let mut write_error: Option<io::Error> = None;
+
+    let result: MatchResult = hmg::play_match(&mut first, &mut second, &config, |game, record|{
+        println!("game {}: {} vs {} {} ({} plies)", game+1, record.white, record.black, record.result, record.moves.len());
+
+        if let Some(file) = out.as_mut(){
+            if let Err(e) = writeln!(file, "{record}"){
+                write_error.get_or_insert(e);
+            }
+        }
+    })?;
+
+    if let Some(e) = write_error{
+        return Err(e.into());
+    }
+
+    println!();
+    println!("W/D/L: {}/{}/{}", result.wins, result.draws, result.losses);
+
+    match result.elo(){
+        Some((elo, margin)) => println!("Elo: {elo:+.1} +/- {margin:.1}"),
+        None => println!("Elo: not enough decisive results for an estimate"),
+    }
+
+    Ok(())
+}
+
+fn run_book(args: &[String]) -> Result<(), Box<dyn Error>>{
+
+    let mut out: Option<String> = None;
+    let mut max_plies: usize = 16;
+    let mut records: Vec<GameRecord> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next(){
+        match arg.as_str(){
+            "--out" => out = Some(args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.clone()),
+            "--max-plies" => max_plies = args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.parse()?,
+            file => records.extend(GameRecord::read_all(&fs::read_to_string(file)?)?),
+        }
+    }
+
+    let out = out.ok_or(format!("missing --out\n{USAGE}"))?;
+
+    let book = OpeningBook::from_records(&records, max_plies);
+    book.write_to(&mut File::create(&out)?)?;
+
+    println!("{} positions from {} games written to {}", book.len(), records.len(), out);
+
+    Ok(())
+}
+
+fn run_tablebase(args: &[String]) -> Result<(), Box<dyn Error>>{
+
+    let mut out: Option<String> = None;
+    let mut materials: Vec<Material> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next(){
+        match arg.as_str(){
+            "--out" => out = Some(args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.clone()),
+            material => materials.push(material.parse()?),
+        }
+    }
+
+    let out = out.ok_or(format!("missing --out\n{USAGE}"))?;
+
+    let mut tablebases = TablebaseSet::new();
+    for material in materials{
+        tablebases.generate(material);
+    }
+
+    for table in tablebases.tables(){
+
+        let (mut wins, mut draws, mut losses, mut longest) = (0, 0, 0, 0);
+
+        for value in table.values(){
+            match value{
+                TablebaseValue::Win(d) => {
+                    wins += 1;
+                    longest = longest.max(d);
+                },
+                TablebaseValue::Draw => draws += 1,
+                TablebaseValue::Loss(d) => {
+                    losses += 1;
+                    longest = longest.max(d);
+                },
+            }
+        }
+
+        println!("{}: {} positions, W/D/L {}/{}/{}, longest mate {} plies", table.material(), wins+draws+losses, wins, draws, losses, longest);
+    }
+
+    tablebases.write_to(&mut io::BufWriter::new(File::create(&out)?))?;
+    println!("{} tables written to {}", tablebases.len(), out);
+
+    Ok(())
+}
+
+fn run_mate(args: &[String]) -> Result<(), Box<dyn Error>>{
+
+    let (n, fen) = args.split_first().ok_or(format!("missing number of moves\n{USAGE}"))?;
+    let n: u32 = n.parse()?;
+
+    // the FEN can be given as one argument or several
+    let position = Position::from_FEN(&fen.join(" "))?;
+
+    if let Err(issues) = position.validate(){
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        return Err(format!("position can't be solved: {}", issues.join(", ")).into());
+    }
+
+    let solution = hmg::solve_mate(&position, n);
+
+    print!("{solution}");
+
+    if solution.is_cooked(){
+        let keys: Vec<String> = solution.keys.iter().map(|k| k.played.to_string()).collect();
+        println!("cooked: {} keys ({})", keys.len(), keys.join(", "));
+    }
+
+    println!("{} nodes", solution.nodes);
+
+    Ok(())
+}
+
+fn run_magics(args: &[String]) -> Result<(), Box<dyn Error>>{
+
+    let mut tables: Vec<MagicTable> = Vec::new();
+    let mut seed: u64 = 3262394871;
+    let mut tries: u32 = 100000;
+    let mut verify = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next(){
+        match arg.as_str(){
+            "--table" => tables.push(args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.parse()?),
+            "--seed" => seed = args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.parse()?,
+            "--tries" => tries = args.next().ok_or(format!("missing value for {arg}\n{USAGE}"))?.parse()?,
+            "--verify" => verify = true,
+            _ => return Err(format!("unknown option {arg}\n{USAGE}").into()),
+        }
+    }
+
+    if seed == 0{
+        return Err("seed must not be 0".into());
+    }
+
+    if tables.is_empty(){
+        tables.extend(MagicTable::ALL);
+    }
+
+    if verify{
+        let failures: Vec<String> = hmg::verify_tables()
+            .into_iter()
+            .filter(|(table, _)| tables.contains(table))
+            .map(|(table, square)| format!("{table} {square}"))
+            .collect();
+
+        if !failures.is_empty(){
+            return Err(format!("bad magics: {}", failures.join(", ")).into());
+        }
+
+        println!("all magics verified");
+        return Ok(());
+    }
+
+    for table in tables{
+        let magics = table.find_magics(seed, tries);
+
+        println!("// {table}: {} -> {} entries", hmg::table_size(&table.current_magics()), hmg::table_size(&magics));
+        println!("{}", table.write_source(&magics));
+    }
+
+    Ok(())
+}
+
+fn interactive() -> io::Result<()>{
     
     println!("Positions");
 
@@ -0,0 +1,273 @@
+use crate::position::Position;
+use crate::r#move::Move;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A move that forces mate, with every legal reply to it
+#[derive(Debug, Clone)]
+pub struct MateLine{
+    pub played: Move,
+    /// moves by the attacker until mate against the best defence, counting this one
+    pub mate_in: u32,
+    /// empty when played is mate
+    pub defences: Vec<Defence>,
+}
+
+/// A reply by the defender and every attacking move that still mates in time
+#[derive(Debug, Clone)]
+pub struct Defence{
+    pub reply: Move,
+    pub continuations: Vec<MateLine>,
+}
+
+/// Every key move of a mate problem and its solution tree
+#[derive(Debug, Clone)]
+pub struct MateSolution{
+    /// the number of moves the problem was solved for
+    pub n: u32,
+    pub keys: Vec<MateLine>,
+    /// positions visited while solving
+    pub nodes: u64,
+}
+impl MateSolution{
+
+    pub fn is_solved(&self) -> bool{
+        !self.keys.is_empty()
+    }
+
+    /// a sound problem has exactly one key
+    pub fn is_cooked(&self) -> bool{
+        self.keys.len() > 1
+    }
+}
+impl fmt::Display for MateSolution{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        if !self.is_solved(){
+            return writeln!(f, "no mate in {}", self.n);
+        }
+
+        for key in &self.keys{
+            writeln!(f, "1. {}! (mate in {})", key.played, key.mate_in)?;
+            write_defences(f, key, 1, 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_defences(f: &mut fmt::Formatter<'_>, line: &MateLine, move_number: u32, depth: usize) -> fmt::Result{
+
+    let indent = "    ".repeat(depth);
+
+    for defence in &line.defences{
+        writeln!(f, "{indent}{move_number}... {}", defence.reply)?;
+
+        for continuation in &defence.continuations{
+            if continuation.defences.is_empty(){
+                writeln!(f, "{indent}    {}. {}#", move_number+1, continuation.played)?;
+            }
+            else{
+                writeln!(f, "{indent}    {}. {}", move_number+1, continuation.played)?;
+                write_defences(f, continuation, move_number+1, depth+2)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds every move for the side to play that forces mate within n moves
+///
+/// the search is exhaustive over legal moves, mate is a position where
+/// Position::is_checkmate holds. the solution tree lists every defence
+/// and every attacking move that keeps the mate in time, so duals show
+/// up as more than one continuation. a position where the side to play
+/// can already take the king has no solution
+pub fn solve_mate(position: &Position, n: u32) -> MateSolution{
+
+    let mut position = position.clone();
+    let mut solver = Solver{
+        cache: HashMap::new(),
+        nodes: 0,
+    };
+
+    let mut result = MateSolution{
+        n,
+        keys: Vec::new(),
+        nodes: 0,
+    };
+
+    if n == 0 || position.is_attacking_king(){
+        return result;
+    }
+
+    for m in legal_moves(&mut position){
+        if solver.wins_after(&mut position, m, n){
+            result.keys.push(solver.line(&mut position, m, n));
+        }
+    }
+
+    // fastest mates first
+    result.keys.sort_by_key(|line| line.mate_in);
+    result.nodes = solver.nodes;
+    result
+}
+
+fn legal_moves(position: &mut Position) -> Vec<Move>{
+    position.generate_legal_moves().into_iter().collect()
+}
+
+struct Solver{
+    /// (zobrist hash, n) -> whether the side to play mates within n
+    cache: HashMap<(u64, u32), bool>,
+    nodes: u64,
+}
+impl Solver{
+
+    /// true if the side to play can force mate within n moves
+    fn mates_in(&mut self, position: &mut Position, n: u32) -> bool{
+
+        if n == 0{
+            return false;
+        }
+
+        let key = (position.zobrist_hash(), n);
+        if let Some(result) = self.cache.get(&key){
+            return *result;
+        }
+
+        let result = legal_moves(position)
+            .into_iter()
+            .any(|m| self.wins_after(position, m, n));
+
+        self.cache.insert(key, result);
+        result
+    }
+
+    /// true if m mates, or every reply to it can be met with mate in n-1
+    fn wins_after(&mut self, position: &mut Position, m: Move, n: u32) -> bool{
+
+        self.nodes += 1;
+        position.make_move(m);
+
+        let result = if n == 1{
+            position.is_checkmate()
+        }
+        else{
+            let replies = legal_moves(position);
+
+            replies.into_iter().all(|r| {
+                position.make_move(r);
+                let mates = self.mates_in(position, n-1);
+                position.unmake_move(r);
+                mates
+            })
+        };
+
+        position.unmake_move(m);
+        result
+    }
+
+    /// solution tree for m, which must mate within n
+    fn line(&mut self, position: &mut Position, m: Move, n: u32) -> MateLine{
+
+        let mate_in = (1..=n).find(|k| self.wins_after(position, m, *k)).unwrap();
+
+        position.make_move(m);
+
+        let mut defences = Vec::new();
+
+        if mate_in > 1{
+            for reply in legal_moves(position){
+                position.make_move(reply);
+
+                let mut continuations = Vec::new();
+                for next in legal_moves(position){
+                    if self.wins_after(position, next, mate_in-1){
+                        continuations.push(self.line(position, next, mate_in-1));
+                    }
+                }
+                continuations.sort_by_key(|line| line.mate_in);
+
+                position.unmake_move(reply);
+                defences.push(Defence{ reply, continuations });
+            }
+        }
+
+        position.unmake_move(m);
+
+        MateLine{ played: m, mate_in, defences }
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    /// checks line mates in time and that every legal defence is answered
+    fn check_line(position: &mut Position, line: &MateLine){
+
+        position.make_move(line.played);
+
+        if line.defences.is_empty(){
+            assert!(position.is_checkmate());
+        }
+        else{
+            assert_eq!(line.defences.len(), legal_moves(position).len());
+
+            for defence in &line.defences{
+                assert!(!defence.continuations.is_empty());
+
+                position.make_move(defence.reply);
+                for continuation in &defence.continuations{
+                    assert!(continuation.mate_in < line.mate_in);
+                    check_line(position, continuation);
+                }
+                position.unmake_move(defence.reply);
+            }
+        }
+
+        position.unmake_move(line.played);
+    }
+
+    #[test]
+    fn mate_in_one_test(){
+
+        // the immobilizer freezes the king from a7 or b7
+        let mut position = Position::from_FEN("k7/7U/2K5/8/8/8/8/8 w 0 1").unwrap();
+        let solution = solve_mate(&position, 1);
+
+        let keys: Vec<String> = solution.keys.iter().map(|k| k.played.to_string()).collect();
+        assert_eq!(keys, ["H7A7", "H7B7"]);
+        assert!(solution.is_cooked());
+
+        for key in &solution.keys{
+            assert_eq!(key.mate_in, 1);
+            check_line(&mut position, key);
+        }
+
+        assert!(solution.to_string().starts_with("1. H7A7! (mate in 1)\n"));
+    }
+
+    #[test]
+    fn mate_in_two_test(){
+
+        // the king on c6 blocks the immobilizer's diagonal
+        let mut position = Position::from_FEN("k7/8/2K5/8/8/8/8/7U w 0 1").unwrap();
+
+        assert!(!solve_mate(&position, 1).is_solved());
+
+        let solution = solve_mate(&position, 2);
+        assert!(solution.is_solved());
+
+        for key in &solution.keys{
+            assert_eq!(key.mate_in, 2);
+            check_line(&mut position, key);
+        }
+
+        assert_eq!(position.to_FEN(), "k7/8/2K5/8/8/8/8/7U w 0 1");
+    }
+}
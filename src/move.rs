@@ -1,13 +1,14 @@
-use crate::types::{Piece, Square};
+use crate::types::{Bitboard, Piece, Square};
+
+use crate::types::ConversionError;
 
 use std::fmt;
-//use std::mem::transmute;
-use std::ops::Index;
+use std::ops::{ControlFlow, Index};
 use std::array::IntoIter;
 use std::slice::Iter;
 use std::iter::Take;
 
-const MAX_MOVES: usize = 256;
+pub(crate) const MAX_MOVES: usize = 256;
 
 type MoveListIntoIter = Take<IntoIter<Move, MAX_MOVES>>;
 type MoveListIter<'a> = Take<Iter<'a, Move>>;
@@ -27,6 +28,10 @@ impl Move{
 
     pub(crate) const EMPTY: Move = Move(0);
 
+    // the getters index Square::ALL and Piece::ALL with masked bits, the
+    // masks keep the index in range and ALL[i] as usize == i (see the
+    // conversion tests in types.rs), so decoding never fails
+
     pub fn is_capture(&self) -> bool{
         ((self.0 & 0x7FFF8000) >> 15) != 0
     }
@@ -39,86 +44,49 @@ impl Move{
     }
 
     pub(crate) fn get_from(&self) -> Square{
-        //(self.0 & 0x3f) as usize
-        /*
-        unsafe{
-            transmute::<usize, Square>((self.0 & 0x3f) as usize)
-        }
-        */
-        Square::try_from((self.0 & 0x3f) as usize).unwrap()
+        Square::ALL[(self.0 & 0x3f) as usize]
     }
     pub(crate) fn set_from(&mut self, s: Square){
         self.0 |= s as u32;
     }
 
     pub(crate) fn get_to(&self) -> Square{
-        //((self.0 & 0xFC0) >> 6) as usize
-        /*
-        unsafe{
-            transmute::<usize, Square>(((self.0 & 0xFC0) >> 6) as usize)
-        }
-        */
-        Square::try_from(((self.0 & 0xFC0) >> 6) as usize).unwrap()
+        Square::ALL[((self.0 & 0xFC0) >> 6) as usize]
     }
     pub(crate) fn set_to(&mut self, s: Square){
         self.0 |= (s as u32) << 6;
     }
 
     pub(crate) fn get_piece(&self) -> Piece{
-        /*
-        unsafe{
-            transmute::<usize, Piece>(((self.0 & 0x7000) >> 12) as usize)
-        }
-        */
-        Piece::try_from(((self.0 & 0x7000) >> 12) as usize).unwrap()
+        Piece::ALL[((self.0 & 0x7000) >> 12) as usize]
     }
     pub(crate) fn set_piece(&mut self, p: Piece){
         self.0 |= (p as u32) << 12;
     }
 
     pub(crate) fn get_c1_piece(&self) -> Piece{
-        /*
-        unsafe{
-            transmute::<usize, Piece>(((self.0 & 0x38000) >> 15) as usize)
-        }
-        */
-        Piece::try_from(((self.0 & 0x38000) >> 15) as usize).unwrap()
+        Piece::ALL[((self.0 & 0x38000) >> 15) as usize]
     }
     pub(crate) fn set_c1_piece(&mut self, p: Piece){
         self.0 |= (p as u32) << 15;
     }
 
     pub(crate) fn get_c2_piece(&self) -> Piece{
-        /*
-        unsafe{
-            transmute::<usize, Piece>(((self.0 & 0x1C0000) >> 18) as usize)
-        }
-        */
-        Piece::try_from(((self.0 & 0x1C0000) >> 18) as usize).unwrap()
+        Piece::ALL[((self.0 & 0x1C0000) >> 18) as usize]
     }
     pub(crate) fn set_c2_piece(&mut self, p: Piece){
         self.0 |= (p as u32) << 18;
     }
 
     pub(crate) fn get_c3_piece(&self) -> Piece{
-        /*
-        unsafe{
-            transmute::<usize, Piece>(((self.0 & 0xE00000) >> 21) as usize)
-        }
-        */
-        Piece::try_from(((self.0 & 0xE00000) >> 21) as usize).unwrap()
+        Piece::ALL[((self.0 & 0xE00000) >> 21) as usize]
     }
     pub(crate) fn set_c3_piece(&mut self, p: Piece){
         self.0 |= (p as u32) << 21;
     }
 
     pub(crate) fn get_c4_piece(&self) -> Piece{
-        /*
-        unsafe{
-            transmute::<usize, Piece>(((self.0 & 0x7000000) >> 24) as usize)
-        }
-        */
-        Piece::try_from(((self.0 & 0x7000000) >> 24) as usize).unwrap()
+        Piece::ALL[((self.0 & 0x7000000) >> 24) as usize]
     }
     pub(crate) fn set_c4_piece(&mut self, p: Piece){
         self.0 |= (p as u32) << 24;
@@ -230,6 +198,60 @@ impl Move{
         self.0 |= (b as u32) << 25;
     }
 
+    // a springer taking more than one piece in a leap stores the last one
+    // taken in c1 as usual, and the others in c2 and c3, nearest to the to
+    // square first. the chain has bit k-1 set for each of those that was k
+    // squares from the from square, which is never more than 4
+
+    pub(crate) fn get_springer_chain(&self) -> u32{
+        (self.0 & 0xF000000) >> 24
+    }
+    /// records an earlier capture of a multi capture leap, piece
+    /// was step squares from the from square
+    pub(crate) fn add_springer_capture(&mut self, step: u32, p: Piece){
+        // more than three pieces with a square behind each never fit on one line
+        debug_assert!(self.get_c3_piece() == Piece::Empty, "no slot left for a springer capture");
+        debug_assert!((1..=4).contains(&step), "springer capture {step} squares away");
+
+        if self.get_c2_piece() == Piece::Empty{
+            self.set_c2_piece(p);
+        }
+        else{
+            self.set_c3_piece(p);
+        }
+        self.0 |= 1 << (step - 1 + 24);
+    }
+
+    // the same for a chameleon leaping over springers, the last is c11
+    pub(crate) fn get_chameleon_chain(&self) -> u32{
+        (self.0 & 0x3C000000) >> 26
+    }
+    pub(crate) fn add_chameleon_springer_capture(&mut self, step: u32){
+        self.0 |= 1 << (step - 1 + 26);
+    }
+
+}
+impl From<Move> for u32{
+    fn from(value: Move) -> Self{
+        value.0
+    }
+}
+/// checks the bits could describe a move, not that the move is legal
+/// anywhere. the moving piece has to be set, from and to have to differ
+/// and the unused top bit has to be clear
+impl TryFrom<u32> for Move{
+    type Error = ConversionError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error>{
+        let m = Move(value);
+
+        if value >> 31 != 0 || m.get_piece() == Piece::Empty || m.get_from() == m.get_to(){
+            Err(ConversionError{})
+        }
+        else{
+            Ok(m)
+        }
+    }
 }
 impl fmt::Display for Move{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
@@ -242,7 +264,7 @@ impl fmt::Debug for Move{
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MoveList{
     moves: [Move; MAX_MOVES],
     size: usize,
@@ -260,6 +282,10 @@ impl MoveList{
         self.size
     }
 
+    pub const fn is_empty(&self) -> bool{
+        self.size == 0
+    }
+
     pub fn get(&self, index: usize) -> Option<Move>{
         if index < self.size{
             Some(self.moves[index])
@@ -279,6 +305,109 @@ impl MoveList{
         self.size += 1;
     }
 }
+impl Default for MoveList{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+impl MoveSink for MoveList{
+    fn push(&mut self, m: Move) -> ControlFlow<()>{
+        self.add_move(m);
+        ControlFlow::Continue(())
+    }
+}
+impl MoveSink for Vec<Move>{
+    fn push(&mut self, m: Move) -> ControlFlow<()>{
+        Vec::push(self, m);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Where Position::generate_moves_into puts the moves it finds
+///
+/// a sink can filter, order or count the moves as they come without
+/// the generator allocating anything, and stops the generation by
+/// returning ControlFlow::Break
+pub trait MoveSink{
+    fn push(&mut self, m: Move) -> ControlFlow<()>;
+}
+
+/// Counts the moves pushed into it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MoveCount(pub usize);
+impl MoveSink for MoveCount{
+    fn push(&mut self, _m: Move) -> ControlFlow<()>{
+        self.0 += 1;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Keeps the first move pushed into it and stops the generation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FirstMove(pub Option<Move>);
+impl MoveSink for FirstMove{
+    fn push(&mut self, m: Move) -> ControlFlow<()>{
+        self.0 = Some(m);
+        ControlFlow::Break(())
+    }
+}
+
+/// Which moves Position::generate_moves_filtered generates
+///
+/// narrowed down from one of the constants, e.g.
+/// GenFilter{ piece: Some(Piece::Springer), ..GenFilter::CAPTURES }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenFilter{
+    /// only moves of this piece, every piece if None
+    pub piece: Option<Piece>,
+    /// moves that capture at least one piece
+    pub captures: bool,
+    /// moves that don't capture anything
+    pub quiet: bool,
+    /// only moves ending on one of these squares
+    pub targets: Bitboard,
+}
+impl GenFilter{
+
+    /// every psuedolegal move, the same as generate_moves
+    pub const ALL: GenFilter = GenFilter{
+        piece: None,
+        captures: true,
+        quiet: true,
+        targets: Bitboard(u64::MAX),
+    };
+
+    pub const CAPTURES: GenFilter = GenFilter{
+        quiet: false,
+        ..GenFilter::ALL
+    };
+
+    pub const QUIET: GenFilter = GenFilter{
+        captures: false,
+        ..GenFilter::ALL
+    };
+
+    /// every square if piece moves are generated, otherwise none, so the
+    /// generator can mask its pieces with it
+    pub(crate) fn movers(&self, piece: Piece) -> Bitboard{
+        match self.piece{
+            Some(p) if p != piece => Bitboard::EMPTY,
+            _ => Bitboard(u64::MAX),
+        }
+    }
+
+    /// true if the filter lets m through
+    pub fn allows(&self, m: Move) -> bool{
+        !self.movers(m.get_piece()).is_empty()
+            && self.targets.contains(m.get_to())
+            && if m.is_capture() {self.captures} else {self.quiet}
+    }
+}
+impl Default for GenFilter{
+    fn default() -> Self{
+        Self::ALL
+    }
+}
 impl Index<usize> for MoveList{
     type Output = Move;
 
@@ -320,6 +449,7 @@ impl FromIterator<Move> for MoveList{
 mod test{
 
     use super::*;
+    use crate::utils::PRNG;
 
     #[test]
     fn simple_move_test(){
@@ -355,4 +485,44 @@ mod test{
         assert_eq!(m.get_piece(), Piece::Springer);
         assert_eq!(m.get_c1_piece(), Piece::Stradler);
     }
+
+    fn decode(bits: u32){
+        match Move::try_from(bits){
+            Ok(m) => {
+                assert_eq!(u32::from(m), bits);
+                assert_ne!(m.get_from(), m.get_to());
+                assert_ne!(m.get_piece(), Piece::Empty);
+
+                // every getter has to cope with whatever is in the capture bits
+                let _ = (m.get_c1_piece(), m.get_c2_piece(), m.get_c3_piece(), m.get_c4_piece());
+                let _ = (m.get_c5_bit(), m.get_c6_bit(), m.get_c7_bit(), m.get_c8_bit(), m.get_chameleon_c11_bit());
+                let _ = format!("{m} {m:?}");
+            },
+            Err(_) => assert!(bits >> 31 != 0 || (bits >> 12) & 7 == 0 || bits & 0x3f == (bits >> 6) & 0x3f),
+        }
+    }
+
+    #[test]
+    fn decode_any_bits_test(){
+
+        // every from, to and piece with a few capture patterns
+        for low in 0..(1u32 << 15){
+            for high in [0, 0x7FFF8000, 0x80000000, 0xFFFF8000]{
+                decode(low | high);
+            }
+        }
+
+        let mut rng = PRNG::new(3262394871);
+        for _ in 0..1_000_000{
+            decode(rng.rand64() as u32);
+        }
+
+        let mut m = Move::EMPTY;
+        m.set_from(Square::E1);
+        m.set_to(Square::H1);
+        m.set_piece(Piece::King);
+
+        assert_eq!(Move::try_from(u32::from(m)).unwrap(), m);
+        assert!(Move::try_from(0).is_err());
+    }
 }
\ No newline at end of file
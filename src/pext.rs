@@ -0,0 +1,306 @@
+// PEXT indexed versions of the magic lookup tables, used instead of
+// the magics when the cpu has BMI2. the tables are the same size as the
+// magic ones, pext only replaces the multiply and shift of the index
+//
+// the carry-rippler loop visits the subsets of a mask in the order of
+// their pext, so while building the tables the index is just a count
+
+use crate::tables::*;
+use crate::types::{Bitboard, Square};
+use crate::utils::lookup_table;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::_pext_u64;
+
+/// true if the pext lookups can be used on this cpu
+#[inline]
+pub(crate) fn bmi2_available() -> bool{
+    // the result is cached by std, this is an atomic load after the first call
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("bmi2")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// the bits of value selected by mask, packed into the low bits
+#[cfg(any(test, not(target_arch = "x86_64")))]
+const fn soft_pext(value: u64, mut mask: u64) -> u64{
+    let mut result: u64 = 0;
+    let mut bit: u64 = 1;
+
+    while mask != 0{
+        if value & mask & mask.wrapping_neg() != 0{
+            result |= bit;
+        }
+        mask &= mask - 1;
+        bit <<= 1;
+    }
+
+    result
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+unsafe fn hardware_pext(value: u64, mask: u64) -> u64{
+    _pext_u64(value, mask)
+}
+
+/// must only be called if bmi2_available() is true
+#[inline(always)]
+fn pext_index(blockers: Bitboard, mask: Bitboard) -> usize{
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: every caller checks bmi2_available first
+        unsafe{ hardware_pext(blockers.0, mask.0) as usize }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        soft_pext(blockers.0, mask.0) as usize
+    }
+}
+
+#[inline]
+pub(crate) fn get_orth_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    PEXT_ORTH_LOOKUPS[pext_index(total_board, ORTH_RELEVANT_BLOCKERS[square]) + PEXT_ORTH_OFFSETS[square]]
+}
+
+#[inline]
+pub(crate) fn get_diag_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    PEXT_DIAG_LOOKUPS[pext_index(total_board, DIAG_RELEVANT_BLOCKERS[square]) + PEXT_DIAG_OFFSETS[square]]
+}
+
+#[inline]
+pub(crate) fn get_potential_stradler_captures(square: Square, friendly_stradlers: Bitboard) -> [Bitboard; 4]{
+    PEXT_STRADLER_LOOKUPS[pext_index(friendly_stradlers, RELEVANT_BUDDIES[square]) + PEXT_STRADLER_OFFSETS[square]]
+}
+
+const fn generate_pext_offsets(masks: &[Bitboard; 64]) -> [usize; 64]{
+    let mut result: [usize; 64] = [0; 64];
+    let mut offset_count: usize = 0;
+    let mut i = 0;
+    while i < 64{
+        result[i] = offset_count;
+        offset_count += 1 << masks[i].0.count_ones();
+        i += 1;
+    }
+    result
+}
+
+// with pext every subset gets its own entry, so these match the magic
+// tables as long as the magics use one index bit per mask square
+const PEXT_ORTH_SIZE: usize = 102400;
+const PEXT_DIAG_SIZE: usize = 5248;
+const PEXT_STRADLER_SIZE: usize = 576;
+
+static PEXT_ORTH_OFFSETS: [usize; 64] = generate_pext_offsets(&ORTH_RELEVANT_BLOCKERS);
+static PEXT_DIAG_OFFSETS: [usize; 64] = generate_pext_offsets(&DIAG_RELEVANT_BLOCKERS);
+static PEXT_STRADLER_OFFSETS: [usize; 64] = generate_pext_offsets(&RELEVANT_BUDDIES);
+
+lookup_table!{
+static PEXT_ORTH_LOOKUPS: [Bitboard; PEXT_ORTH_SIZE] = {
+    let mut result: [Bitboard; PEXT_ORTH_SIZE] = [Bitboard::UNUSED; PEXT_ORTH_SIZE];
+
+    let mut i = 0;
+    while i < 64{
+
+        let relevant_blockers: Bitboard = ORTH_RELEVANT_BLOCKERS[i];
+        let mut blocker_subset: Bitboard = Bitboard::EMPTY;
+
+        let mut j = 0;
+        while j < (1 << relevant_blockers.0.count_ones()){
+            blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
+
+            let index = (j + 1) & ((1 << relevant_blockers.0.count_ones()) - 1);
+
+            result[index + PEXT_ORTH_OFFSETS[i]] = generate_orthogonal_lookup(Square::ALL[i], blocker_subset);
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    result
+};
+}
+
+lookup_table!{
+static PEXT_DIAG_LOOKUPS: [Bitboard; PEXT_DIAG_SIZE] = {
+    let mut result: [Bitboard; PEXT_DIAG_SIZE] = [Bitboard::UNUSED; PEXT_DIAG_SIZE];
+
+    let mut i = 0;
+    while i < 64{
+
+        let relevant_blockers: Bitboard = DIAG_RELEVANT_BLOCKERS[i];
+        let mut blocker_subset: Bitboard = Bitboard::EMPTY;
+
+        let mut j = 0;
+        while j < (1 << relevant_blockers.0.count_ones()){
+            blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
+
+            let index = (j + 1) & ((1 << relevant_blockers.0.count_ones()) - 1);
+
+            result[index + PEXT_DIAG_OFFSETS[i]] = generate_diagonal_lookup(Square::ALL[i], blocker_subset);
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    result
+};
+}
+
+lookup_table!{
+static PEXT_STRADLER_LOOKUPS: [[Bitboard; 4]; PEXT_STRADLER_SIZE] = {
+    let mut result: [[Bitboard; 4]; PEXT_STRADLER_SIZE] = [[Bitboard::UNUSED; 4]; PEXT_STRADLER_SIZE];
+
+    let mut i = 0;
+    while i < 64{
+
+        let relevant_blockers: Bitboard = RELEVANT_BUDDIES[i];
+        let mut blocker_subset: Bitboard = Bitboard::EMPTY;
+
+        let mut j = 0;
+        while j < (1 << relevant_blockers.0.count_ones()){
+            blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
+
+            let index = (j + 1) & ((1 << relevant_blockers.0.count_ones()) - 1);
+
+            result[index + PEXT_STRADLER_OFFSETS[i]] = generate_stradler_captures(Square::ALL[i], blocker_subset);
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    result
+};
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    // every subset of mask, starting and ending with the empty set
+    fn subsets(mask: Bitboard) -> impl Iterator<Item = Bitboard>{
+        let mut subset = Some(Bitboard::EMPTY);
+
+        std::iter::from_fn(move || {
+            let current = subset?;
+            let next = Bitboard(current.0.wrapping_sub(mask.0) & mask.0);
+            subset = if next.is_empty(){ None } else { Some(next) };
+            Some(current)
+        })
+    }
+
+    fn magic_orth(square: Square, blockers: Bitboard) -> Bitboard{
+        ORTH_LOOKUPS[magic_index(blockers & ORTH_RELEVANT_BLOCKERS[square], ORTH_MAGICS[square], ORTH_THROWAWAY[square]) + ORTH_OFFSETS[square]]
+    }
+
+    fn magic_diag(square: Square, blockers: Bitboard) -> Bitboard{
+        DIAG_LOOKUPS[magic_index(blockers & DIAG_RELEVANT_BLOCKERS[square], DIAG_MAGICS[square], DIAG_THROWAWAY[square]) + DIAG_OFFSETS[square]]
+    }
+
+    fn magic_stradler(square: Square, buddies: Bitboard) -> [Bitboard; 4]{
+        STRADLER_LOOKUPS[magic_index(buddies & RELEVANT_BUDDIES[square], STRADLER_MAGICS[square], STRADLER_THROWAWAY[square]) + STRADLER_OFFSETS[square]]
+    }
+
+    #[test]
+    fn table_size_test(){
+        assert_eq!(PEXT_ORTH_OFFSETS[63] + (1 << ORTH_RELEVANT_BLOCKERS[63].0.count_ones()), PEXT_ORTH_SIZE);
+        assert_eq!(PEXT_DIAG_OFFSETS[63] + (1 << DIAG_RELEVANT_BLOCKERS[63].0.count_ones()), PEXT_DIAG_SIZE);
+        assert_eq!(PEXT_STRADLER_OFFSETS[63] + (1 << RELEVANT_BUDDIES[63].0.count_ones()), PEXT_STRADLER_SIZE);
+    }
+
+    #[test]
+    fn soft_pext_test(){
+        assert_eq!(soft_pext(0b1011_0110, 0b1111_0000), 0b1011);
+        assert_eq!(soft_pext(0b1011_0110, 0b0101_0101), 0b0110);
+        assert_eq!(soft_pext(u64::MAX, 0), 0);
+
+        // the tables are built on subsets coming in pext order
+        for square in Square::ALL{
+            let mask = ORTH_RELEVANT_BLOCKERS[square] | DIAG_RELEVANT_BLOCKERS[square];
+            for (i, subset) in subsets(mask).enumerate(){
+                assert_eq!(soft_pext(subset.0, mask.0) as usize, i);
+
+                if bmi2_available(){
+                    assert_eq!(pext_index(subset, mask), i);
+                }
+            }
+        }
+    }
+
+    fn soft_orth(square: Square, blockers: Bitboard) -> Bitboard{
+        PEXT_ORTH_LOOKUPS[soft_pext(blockers.0, ORTH_RELEVANT_BLOCKERS[square].0) as usize + PEXT_ORTH_OFFSETS[square]]
+    }
+
+    fn soft_diag(square: Square, blockers: Bitboard) -> Bitboard{
+        PEXT_DIAG_LOOKUPS[soft_pext(blockers.0, DIAG_RELEVANT_BLOCKERS[square].0) as usize + PEXT_DIAG_OFFSETS[square]]
+    }
+
+    fn soft_stradler(square: Square, buddies: Bitboard) -> [Bitboard; 4]{
+        PEXT_STRADLER_LOOKUPS[soft_pext(buddies.0, RELEVANT_BUDDIES[square].0) as usize + PEXT_STRADLER_OFFSETS[square]]
+    }
+
+    #[test]
+    fn backend_equivalence_test(){
+
+        // the tables are always checked through the software pext, the
+        // hardware lookups are only checked on cpus that have bmi2
+        let hardware = bmi2_available();
+
+        for square in Square::ALL{
+
+            // the unmasked squares are set so ignoring them is checked too
+            for subset in subsets(ORTH_RELEVANT_BLOCKERS[square]){
+                let board = subset | !ORTH_RELEVANT_BLOCKERS[square];
+                assert_eq!(soft_orth(square, subset), magic_orth(square, subset));
+                assert_eq!(soft_orth(square, board), magic_orth(square, board));
+
+                if hardware{
+                    assert_eq!(get_orth_moves(square, subset), magic_orth(square, subset));
+                    assert_eq!(get_orth_moves(square, board), magic_orth(square, board));
+                }
+            }
+
+            for subset in subsets(DIAG_RELEVANT_BLOCKERS[square]){
+                let board = subset | !DIAG_RELEVANT_BLOCKERS[square];
+                assert_eq!(soft_diag(square, subset), magic_diag(square, subset));
+                assert_eq!(soft_diag(square, board), magic_diag(square, board));
+
+                if hardware{
+                    assert_eq!(get_diag_moves(square, subset), magic_diag(square, subset));
+                    assert_eq!(get_diag_moves(square, board), magic_diag(square, board));
+                }
+            }
+
+            for subset in subsets(RELEVANT_BUDDIES[square]){
+                let board = subset | !RELEVANT_BUDDIES[square];
+                assert_eq!(soft_stradler(square, subset), magic_stradler(square, subset));
+                assert_eq!(soft_stradler(square, board), magic_stradler(square, board));
+
+                if hardware{
+                    assert_eq!(get_potential_stradler_captures(square, subset), magic_stradler(square, subset));
+                    assert_eq!(get_potential_stradler_captures(square, board), magic_stradler(square, board));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn table_footprint_test(){
+        // pext saves the magic multiply, not memory: the tables are the same size
+        assert_eq!(PEXT_ORTH_SIZE, ORTH_LOOKUP_SIZE);
+        assert_eq!(PEXT_DIAG_SIZE, DIAG_LOOKUP_SIZE);
+        assert_eq!(PEXT_STRADLER_SIZE, STRADLER_LOOKUP_SIZE);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::types::{Bitboard, Color, Piece, Square};
-use crate::r#move::{Move, MoveList};
+use crate::r#move::{Move, MoveList, MoveSink, FirstMove, GenFilter, MAX_MOVES};
+use crate::zobrist::{piece_key, side_key, rules_key};
+use crate::rules::Rules;
 use crate::tables::{get_orth_moves, get_diag_moves, get_potential_stradler_captures, get_king_moves, get_death_squares, get_springer_landing_square, get_retractor_lookup, get_springer_captured_square};
 //use crate::tables::*;
 
 use std::fmt;
 use std::num::ParseIntError;
+use std::ops::ControlFlow;
 use std::error;
 
 #[derive(Debug)]
@@ -23,6 +26,157 @@ impl error::Error for ReadFenError{}
 
 type Result<T> = std::result::Result<T, ReadFenError>;
 
+#[derive(Debug)]
+pub struct ReadBytesError{}
+impl fmt::Display for ReadBytesError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading position bytes")
+    }
+}
+impl error::Error for ReadBytesError{}
+
+/// length of the encoding written by Position::to_bytes
+pub const POSITION_BYTES: usize = 50;
+
+/// first byte of to_bytes, changed whenever the layout changes
+pub const BYTES_VERSION: u8 = 1;
+
+#[derive(Debug)]
+pub struct IllegalMove{}
+impl fmt::Display for IllegalMove{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Move is not legal in the current position")
+    }
+}
+impl error::Error for IllegalMove{}
+
+/// Something about a position that generate_moves or make_move can't
+/// handle, found by Position::validate
+///
+/// pieces are written as in FEN, so 'K' is the white king and 'k' the black one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionIssue{
+    /// the side doesn't have exactly one king, with the number it has
+    KingCount(char, u32),
+    /// more of the piece than generate_moves can move, with the number there is
+    TooMany(char, u32),
+    /// the side to play could capture this king
+    KingCapturable(char),
+    /// the mailbox, bitboards and hash disagree
+    Inconsistent,
+}
+impl fmt::Display for PositionIssue{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::KingCount(king, count) => write!(f, "{count} of {king}, there has to be exactly one"),
+            Self::TooMany(piece, count) => write!(f, "{count} of {piece}, more than can be moved"),
+            Self::KingCapturable(king) => write!(f, "{king} can be captured by the side to play"),
+            Self::Inconsistent => write!(f, "internal state is inconsistent"),
+        }
+    }
+}
+
+/// A move played with try_make_move, taken back by passing it to undo_move
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Undo{
+    m: Move,
+    // hash and move counters of the position the move led to
+    zobrist_hash: u64,
+    halfmoves: u32,
+    fullmoves: u32,
+}
+impl Undo{
+    pub fn get_move(&self) -> Move{
+        self.m
+    }
+}
+
+/// What the pieces of one side threaten, from Position::threats
+#[derive(Clone)]
+pub struct ThreatMap{
+    /// the psuedolegal capturing moves
+    pub captures: MoveList,
+    /// the squares each of captures takes a piece on, in the same order
+    taken: [Bitboard; MAX_MOVES],
+    /// the pieces that can take the enemy piece on each square, indexed by Square
+    pub attackers: [Bitboard; 64],
+    /// enemy pieces that can't move
+    pub immobilized: Bitboard,
+    /// squares the next coordinator or king move can make into a death
+    /// square, an enemy piece moved there can be taken with that move
+    pub death_squares: Bitboard,
+}
+impl ThreatMap{
+
+    /// the enemy pieces that can be taken
+    pub fn attacked(&self) -> Bitboard{
+        Square::ALL.into_iter().filter(|s| !self.attackers[*s].is_empty()).collect()
+    }
+
+    /// the capturing moves that take the piece on square
+    pub fn captures_on(&self, square: Square) -> impl Iterator<Item = Move> + '_{
+        self.captures.iter().zip(self.taken.iter()).filter(move |(_, taken)| taken.contains(square)).map(|(m, _)| *m)
+    }
+}
+impl fmt::Debug for ThreatMap{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        f.debug_struct("ThreatMap")
+            .field("captures", &self.captures.iter().zip(self.taken.iter()).collect::<Vec<_>>())
+            .field("immobilized", &self.immobilized)
+            .field("death_squares", &self.death_squares)
+            .finish()
+    }
+}
+
+const ANY_PIECE: &[Piece] = &[Piece::Stradler, Piece::Coordinator, Piece::Springer, Piece::Chameleon, Piece::Retractor, Piece::Immobilizer, Piece::King];
+
+/// most of each piece a side can have for generate_moves to work,
+/// there is room for two chameleons and only the first coordinator,
+/// retractor, immobilizer or king is ever moved
+const MAX_COUNT: [u32; 8] = [0, 64, 1, 64, 2, 1, 1, 1];
+
+/// A square a move could have captured on, the pieces that could
+/// have been there and how the move records taking one
+struct Uncapture{
+    square: Square,
+    pieces: &'static [Piece],
+    record: fn(&mut Move, Piece),
+}
+
+/// squares step squares from from towards to for every bit step-1 set
+/// in chain, nearest to to first. used for the pieces taken on the way
+/// by a springer leap with more than one capture
+fn chain_squares(from: Square, to: Square, chain: u32) -> impl Iterator<Item = Square>{
+    chain_steps(from, to).filter(move |(_, step)| chain & (1 << (step - 1)) != 0).map(|(s, _)| s)
+}
+
+/// every square a springer leaping from -> to could have taken a piece
+/// on before the last one, nearest to to first, with its distance from from
+fn chain_steps(from: Square, to: Square) -> impl Iterator<Item = (Square, usize)>{
+    let dx = (to.file() as i8 - from.file() as i8).signum();
+    let dy = (to.rank() as i8 - from.rank() as i8).signum();
+
+    // the last piece taken and the one before it each need a square behind them
+    let last = (from.distance(to) as usize).saturating_sub(3).min(4);
+
+    (1..=last).rev().filter_map(move |step| from.offset(dx*step as i8, dy*step as i8).map(|s| (s, step)))
+}
+
+const SPRINGER_CHAIN_RECORDS: [fn(&mut Move, Piece); 4] = [
+    |m, p| m.add_springer_capture(1, p),
+    |m, p| m.add_springer_capture(2, p),
+    |m, p| m.add_springer_capture(3, p),
+    |m, p| m.add_springer_capture(4, p),
+];
+
+const CHAMELEON_CHAIN_RECORDS: [fn(&mut Move, Piece); 4] = [
+    |m, _| m.add_chameleon_springer_capture(1),
+    |m, _| m.add_chameleon_springer_capture(2),
+    |m, _| m.add_chameleon_springer_capture(3),
+    |m, _| m.add_chameleon_springer_capture(4),
+];
+
 #[derive(Clone)]
 pub struct Position{
     board: [Piece; 64],
@@ -31,11 +185,10 @@ pub struct Position{
     to_play: Color,
     halfmoves: u32,
     fullmoves: u32,
+    rules: Rules,
 }
 impl Position{
 
-    const STARTING_FEN: &'static str = "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1";
-
     fn create_empty() -> Self{
         Position{ 
             board: [Piece::Empty; 64],
@@ -44,11 +197,29 @@ impl Position{
             to_play: Color::White, 
             halfmoves: 0,
             fullmoves: 0,
+            rules: Rules::default(),
         }
     }
 
     pub fn from_start_position() -> Self{
-        Self::from_FEN(Self::STARTING_FEN).unwrap()
+        Self::from_start_position_with_rules(Rules::default())
+    }
+
+    /// the starting position of the given rule set, played by those rules
+    pub fn from_start_position_with_rules(rules: Rules) -> Self{
+        let mut result = Self::from_FEN(rules.starting_fen()).unwrap();
+        result.set_rules(rules);
+        result
+    }
+
+    /// the rules the position is played by, from_FEN uses Rules::default()
+    pub fn rules(&self) -> Rules{
+        self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Rules){
+        self.zobrist_hash ^= rules_key(self.rules) ^ rules_key(rules);
+        self.rules = rules;
     }
 
     #[allow(non_snake_case)]
@@ -67,8 +238,12 @@ impl Position{
         // read pieces
         while let Some(mut piece) = fen_position.next(){
 
+            // every rank has to add up to exactly 8 files
             if piece.is_ascii_digit(){
                 file += piece.to_digit(10).unwrap();
+                if piece == '0' || file > 8{
+                    return Err(ReadFenError{});
+                }
                 continue;
             }
 
@@ -85,6 +260,9 @@ impl Position{
                 'u' => Piece::Immobilizer,
                 'k' => Piece::King,
                 '/' => {
+                    if rank == 0 || file != 8{
+                        return Err(ReadFenError{});
+                    }
                     rank -= 1;
                     file = 0;
                     continue;
@@ -93,6 +271,10 @@ impl Position{
                 _ => return Err(ReadFenError{})
             };
 
+            if file >= 8{
+                return Err(ReadFenError{});
+            }
+
             let s = (rank*8 + file) as usize;
             result.board[s] = piece_index;
             result.bitboards[color | piece_index] |= Bitboard(1 << (rank*8 + file));
@@ -102,6 +284,10 @@ impl Position{
 
         }
 
+        if rank != 0 || file != 8{
+            return Err(ReadFenError{});
+        }
+
         result.to_play = match fen_parts.next(){
             Some("w") => Color::White,
             Some("b") => Color::Black,
@@ -124,12 +310,271 @@ impl Position{
 
         result.fullmoves = fullmoves.parse()?;
 
+        result.zobrist_hash = result.compute_zobrist_hash();
+
         Ok(result)
     }
 
+    /// Writes the position in the same FEN format read by from_FEN
+    ///
+    /// the rules are left out, FEN has no field for them and other programs
+    /// reading it wouldn't expect one. to_bytes keeps them
+    #[allow(non_snake_case)]
+    pub fn to_FEN(&self) -> String{
+
+        let mut result = String::new();
+
+        for rank in (0..8).rev(){
+
+            let mut empty = 0;
+
+            for file in 0..8{
+
+                let s = rank*8 + file;
+
+                if self.board[s] == Piece::Empty{
+                    empty += 1;
+                    continue;
+                }
+
+                if empty != 0{
+                    result.push_str(&empty.to_string());
+                    empty = 0;
+                }
+
+                let color = if self.bitboards[Color::White].get_bit(s) == 1 {Color::White} else {Color::Black};
+                result.push(Piece::PIECE_SYMBOLS[color | self.board[s]]);
+            }
+
+            if empty != 0{
+                result.push_str(&empty.to_string());
+            }
+            if rank != 0{
+                result.push('/');
+            }
+        }
+
+        let to_play = match self.to_play{
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+
+        format!("{} {} {} {}", result, to_play, self.halfmoves, self.fullmoves)
+    }
+
+    /// Packs the position into POSITION_BYTES bytes, read back by from_bytes
+    ///
+    /// numbers are big endian, so sorting the encodings sorts by piece
+    /// placement first and every position has exactly one encoding:
+    ///
+    /// ```text
+    /// 0       BYTES_VERSION
+    /// 1..9    occupancy bitboard
+    /// 9..41   a nibble of color | piece for each occupied square from A1
+    ///         up, high nibble first, the nibbles after the last piece are 0
+    /// 41      bit 0 set if black is to play, bits 1 to 3 are the rules
+    ///         chameleon_immobilizes_immobilizer, springer_multi_capture
+    ///         and immobilizer_on_left
+    /// 42..46  halfmoves
+    /// 46..50  fullmoves
+    /// ```
+    pub fn to_bytes(&self) -> [u8; POSITION_BYTES]{
+
+        let mut result = [0; POSITION_BYTES];
+        result[0] = BYTES_VERSION;
+
+        let occupancy = self.bitboards[Color::White] | self.bitboards[Color::Black];
+        result[1..9].copy_from_slice(&occupancy.0.to_be_bytes());
+
+        for (i, s) in occupancy.into_iter().enumerate(){
+            let color = if self.bitboards[Color::White].contains(s) {Color::White} else {Color::Black};
+            result[9 + i/2] |= ((color | self.board[s]) as u8) << (4 - 4*(i % 2));
+        }
+
+        result[41] = (self.to_play == Color::Black) as u8
+            | (self.rules.chameleon_immobilizes_immobilizer as u8) << 1
+            | (self.rules.springer_multi_capture as u8) << 2
+            | (self.rules.immobilizer_on_left as u8) << 3;
+
+        result[42..46].copy_from_slice(&self.halfmoves.to_be_bytes());
+        result[46..50].copy_from_slice(&self.fullmoves.to_be_bytes());
+
+        result
+    }
+
+    /// Reads a position written by to_bytes
+    ///
+    /// anything to_bytes could not have written is an error, including
+    /// unused nibbles or flag bits that aren't 0. the position itself is
+    /// not checked, use validate for that
+    pub fn from_bytes(bytes: &[u8]) -> std::result::Result<Self, ReadBytesError>{
+
+        let bytes: &[u8; POSITION_BYTES] = bytes.try_into().map_err(|_| ReadBytesError{})?;
+
+        if bytes[0] != BYTES_VERSION || bytes[41] & 0xF0 != 0{
+            return Err(ReadBytesError{});
+        }
+
+        let mut result = Self::create_empty();
+
+        let occupancy = Bitboard(u64::from_be_bytes(bytes[1..9].try_into().unwrap()));
+        let nibble = |i: usize| (bytes[9 + i/2] >> (4 - 4*(i % 2))) & 0xF;
+
+        for (i, s) in occupancy.into_iter().enumerate(){
+            let color = if nibble(i) & 8 == 0 {Color::White} else {Color::Black};
+
+            match Piece::try_from((nibble(i) & 7) as usize){
+                Ok(Piece::Empty) | Err(_) => return Err(ReadBytesError{}),
+                Ok(piece) => result.place_piece(color, piece, s),
+            }
+        }
+
+        if (occupancy.count() as usize..64).any(|i| nibble(i) != 0){
+            return Err(ReadBytesError{});
+        }
+
+        result.to_play = if bytes[41] & 1 == 0 {Color::White} else {Color::Black};
+        result.rules = Rules{
+            chameleon_immobilizes_immobilizer: bytes[41] & 2 != 0,
+            springer_multi_capture: bytes[41] & 4 != 0,
+            immobilizer_on_left: bytes[41] & 8 != 0,
+        };
+
+        result.halfmoves = u32::from_be_bytes(bytes[42..46].try_into().unwrap());
+        result.fullmoves = u32::from_be_bytes(bytes[46..50].try_into().unwrap());
+
+        result.zobrist_hash = result.compute_zobrist_hash();
+
+        Ok(result)
+    }
+
+    /// Reads a position written by to_bytes, only if validate accepts it
+    ///
+    /// for bytes from outside the program, from_bytes alone reads e.g. a
+    /// board full of kings. use from_bytes and validate to see what's wrong
+    pub fn from_bytes_validated(bytes: &[u8]) -> std::result::Result<Self, ReadBytesError>{
+        let result = Self::from_bytes(bytes)?;
+        result.validate().map_err(|_| ReadBytesError{})?;
+        Ok(result)
+    }
+
+    /// Finds the move described by coordinate notation (e.g. "e2e4")
+    /// among the psuedolegal moves of the current position
+    /// 
+    /// from and to squares are enough to identify a move, as the capture
+    /// bits are determined by the position
+    pub fn parse_move(&self, notation: &str) -> Option<Move>{
+        self.generate_moves()
+            .into_iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(notation.trim()))
+    }
+
+    /// hash of the piece placement, side to play and the rules that change
+    /// how pieces move, updated incrementally by make_move and unmake_move
+    pub fn zobrist_hash(&self) -> u64{
+        self.zobrist_hash
+    }
+
+    fn compute_zobrist_hash(&self) -> u64{
+
+        let mut hash = 0;
+
+        for c in [Color::White, Color::Black]{
+            for p in &(Piece::ALL)[1..]{
+                let mut piece_bb = self.bitboards[c | *p];
+                while !piece_bb.is_empty(){
+                    hash ^= piece_key(c, *p, piece_bb.pop_lsb_square());
+                }
+            }
+        }
+
+        if self.to_play == Color::Black{
+            hash ^= side_key();
+        }
+
+        hash ^ rules_key(self.rules)
+    }
+
+    pub(crate) fn side_to_move(&self) -> Color{
+        self.to_play
+    }
+
+    pub(crate) fn pieces(&self, color: Color, piece: Piece) -> Bitboard{
+        self.bitboards[color | piece]
+    }
+
+    /// copy of the position with a different side to play
+    pub(crate) fn with_side_to_move(&self, color: Color) -> Position{
+        let mut result = self.clone();
+        if result.to_play != color{
+            result.to_play = color;
+            result.zobrist_hash ^= side_key();
+        }
+        result
+    }
+
+    /// The position with the board mirrored top to bottom, the colors of
+    /// the pieces swapped and the other side to play
+    ///
+    /// every piece moves the same way in every direction, so perft counts
+    /// and checkmates don't change. the starting position is not its own
+    /// flip, as the immobilizer stays on the same file it flips into the
+    /// Ultima starting position with black to play
+    pub fn flipped(&self) -> Position{
+        self.transformed(|s| s ^ 56, true)
+    }
+
+    /// The position with the board mirrored left to right
+    pub fn mirrored_horizontal(&self) -> Position{
+        self.transformed(|s| s ^ 7, false)
+    }
+
+    // moves every piece to map(square), swapping colors and the side to play if swap_colors
+    fn transformed(&self, map: impl Fn(usize) -> usize, swap_colors: bool) -> Position{
+
+        let mut result = Self::create_empty();
+
+        result.to_play = if swap_colors {!self.to_play} else {self.to_play};
+        result.halfmoves = self.halfmoves;
+        result.fullmoves = self.fullmoves;
+        result.rules = self.rules;
+
+        for color in [Color::White, Color::Black]{
+            let new_color = if swap_colors {!color} else {color};
+
+            for piece in &Piece::ALL[1..]{
+                for s in self.bitboards[color | *piece]{
+                    result.place_piece(new_color, *piece, Square::ALL[map(s as usize)]);
+                }
+            }
+        }
+
+        result.zobrist_hash = result.compute_zobrist_hash();
+        result
+    }
+
+    /// Position with only the given pieces on the board, squares are assumed distinct
+    pub(crate) fn from_pieces(to_play: Color, pieces: &[(Color, Piece, Square)]) -> Position{
+
+        let mut result = Self::create_empty();
+        result.to_play = to_play;
+
+        for (color, piece, square) in pieces{
+            result.place_piece(*color, *piece, *square);
+        }
+
+        result.zobrist_hash = result.compute_zobrist_hash();
+        result
+    }
+
     /// Place a piece of specified color and type at square, assumes target square is empty
     fn place_piece(&mut self, color: Color, piece: Piece, square: Square){
         assert!(piece != Piece::Empty);
+        // two capture bits can name the same square (e.g. a piece caught
+        // between the king and both chameleons), only hash it once
+        if (self.bitboards[color | piece] & Bitboard::from(square)).is_empty(){
+            self.zobrist_hash ^= piece_key(color, piece, square);
+        }
         self.bitboards[color] |= square.into();
         self.bitboards[color | piece] |= square.into();
         self.board[square] = piece;
@@ -137,6 +582,10 @@ impl Position{
 
     /// Place a piece of specified color and type at square, assumes target square has piece
     fn remove_piece(&mut self, color: Color, piece: Piece, square: Square){
+        // the second capture bit naming the same square finds it already empty
+        if !(self.bitboards[color | piece] & Bitboard::from(square)).is_empty(){
+            self.zobrist_hash ^= piece_key(color, piece, square);
+        }
         self.bitboards[color] &=  !(Bitboard::from(square));
         self.bitboards[color | piece] &=  !(Bitboard::from(square));
         self.board[square] = Piece::Empty;
@@ -145,35 +594,285 @@ impl Position{
     /// Returns a MoveList containing all the psuedolegal moves 
     /// from the curruent position
     pub fn generate_moves(&self) -> MoveList{
+        let mut move_list = MoveList::new();
+        self.generate_moves_into(&mut move_list);
+        move_list
+    }
 
+    /// Pushes the psuedolegal moves into sink, stopping early if it breaks
+    pub fn generate_moves_into<S: MoveSink + ?Sized>(&self, sink: &mut S){
+        let _ = self.push_moves::<_, false>(sink, &GenFilter::ALL);
+    }
+
+    /// Returns a MoveList of the psuedolegal moves filter allows
+    ///
+    /// pieces, squares and kinds of move the filter rules out are skipped
+    /// before their captures are worked out, so asking for less is cheaper
+    pub fn generate_moves_filtered(&self, filter: GenFilter) -> MoveList{
         let mut move_list = MoveList::new();
+        let _ = self.push_moves::<_, true>(&mut move_list, &filter);
+        move_list
+    }
 
-        let not_to_play = !self.to_play;
-        let king_square = self.bitboards[self.to_play | Piece::King].bitscanforward_square();
-        
-        //let immobilized = get_king_moves((self.bitboards[not_to_play | Piece::Immobilizer]).bitscanforward_square());
-        let immobilized = if self.bitboards[not_to_play | Piece::Immobilizer].is_empty(){
+    /// Returns a MoveList containing only the legal moves
+    pub fn generate_legal_moves(&mut self) -> MoveList{
+        let mut move_list = MoveList::new();
+        self.generate_legal_moves_into(&mut move_list);
+        move_list
+    }
+
+    /// Pushes the legal moves into sink, stopping early if it breaks
+    ///
+    /// the psuedolegal moves are checked one at a time with make_move and
+    /// unmake_move, so a sink that stops at the first move skips checking
+    /// the rest. the position is left as it was
+    pub fn generate_legal_moves_into<S: MoveSink + ?Sized>(&mut self, sink: &mut S){
+        for m in self.generate_moves(){
+            if self.is_move_legal(m) && sink.push(m).is_break(){
+                return;
+            }
+        }
+    }
+
+    /// true if the side to play has a legal move
+    pub fn has_legal_move(&mut self) -> bool{
+        let mut first = FirstMove::default();
+        self.generate_legal_moves_into(&mut first);
+        first.0.is_some()
+    }
+
+    /// What the pieces of color threaten, whether or not it is their turn
+    ///
+    /// captures are the psuedolegal capturing moves of color, so a piece
+    /// can be listed as attacked by a move that is illegal
+    pub fn threats(&self, color: Color) -> ThreatMap{
+
+        let enemy = !color;
+
+        let captures = self.with_side_to_move(color).generate_moves_filtered(GenFilter::CAPTURES);
+        let mut taken = [Bitboard::EMPTY; MAX_MOVES];
+        let mut attackers = [Bitboard::EMPTY; 64];
+        for (i, m) in captures.iter().enumerate(){
+            taken[i] = self.captured_squares(color, *m);
+            for s in taken[i]{
+                attackers[s] |= Bitboard::from(m.get_from());
+            }
+        }
+
+        let immobilized = self.frozen(enemy);
+
+        let mut death_squares = Bitboard::EMPTY;
+        let coordinator = self.bitboards[color | Piece::Coordinator];
+        let king = self.bitboards[color | Piece::King];
+
+        if !coordinator.is_empty() && !king.is_empty(){
+            let coordinator = coordinator.bitscanforward_square();
+            let king = king.bitscanforward_square();
+
+            let total_board = self.bitboards[Color::White] | self.bitboards[Color::Black];
+            let frozen = self.frozen(color);
+
+            if !frozen.contains(coordinator){
+                for to in (get_orth_moves(coordinator, total_board) | get_diag_moves(coordinator, total_board)) & !total_board{
+                    let [a, b] = get_death_squares(to, king);
+                    death_squares |= a | b;
+                }
+            }
+            if !frozen.contains(king){
+                for to in get_king_moves(king) & !self.bitboards[color]{
+                    let [a, b] = get_death_squares(to, coordinator);
+                    death_squares |= a | b;
+                }
+            }
+
+            death_squares &= !self.bitboards[color];
+        }
+
+        ThreatMap{ captures, taken, attackers, immobilized, death_squares }
+    }
+
+    /// the pieces of color that can't move, everything next to the enemy
+    /// immobilizer and, when the rules say so, an immobilizer next to an
+    /// enemy chameleon
+    fn frozen(&self, color: Color) -> Bitboard{
+        let enemy = !color;
+
+        let immobilized = if self.bitboards[enemy | Piece::Immobilizer].is_empty(){
             Bitboard::EMPTY
         }else{
-            get_king_moves((self.bitboards[not_to_play | Piece::Immobilizer]).bitscanforward_square())
+            get_king_moves((self.bitboards[enemy | Piece::Immobilizer]).bitscanforward_square())
+        };
+
+        let chameleon_immobilzed: Bitboard = if !self.rules.chameleon_immobilizes_immobilizer{
+            Bitboard::EMPTY
+        }
+        else{
+            let mut chameleons = self.bitboards[enemy | Piece::Chameleon];
+            let mut result = Bitboard(0);
+            while !chameleons.is_empty(){
+                result |= get_king_moves(chameleons.pop_lsb_square());
+            }
+            result
         };
 
+        (immobilized & self.bitboards[color]) | (chameleon_immobilzed & self.bitboards[color | Piece::Immobilizer])
+    }
+
+    /// the squares a move of color takes pieces on, read from the capture
+    /// bits the same way make_move reads them
+    fn captured_squares(&self, color: Color, m: Move) -> Bitboard{
+        let from = m.get_from();
+        let to = m.get_to();
+        let king_square = self.bitboards[color | Piece::King].bitscanforward_square();
+        let coordinator = self.bitboards[color | Piece::Coordinator];
+
+        let mut taken = Bitboard::EMPTY;
+
+        match m.get_piece(){
+            Piece::Empty | Piece::Immobilizer => {},
+            Piece::Stradler => {
+                let maybe_captures = get_potential_stradler_captures(to, Bitboard::UNUSED & !Bitboard::from(from));
+                for (c, s) in [m.get_c1_piece(), m.get_c2_piece(), m.get_c3_piece(), m.get_c4_piece()].into_iter().zip(maybe_captures){
+                    if c != Piece::Empty{
+                        taken |= s;
+                    }
+                }
+            },
+            Piece::Coordinator => {
+                let [a, b] = get_death_squares(to, king_square);
+                if m.get_c1_piece() != Piece::Empty{
+                    taken |= a;
+                }
+                if m.get_c2_piece() != Piece::Empty{
+                    taken |= b;
+                }
+
+                let bits = [m.get_c5_bit(), m.get_c6_bit(), m.get_c7_bit(), m.get_c8_bit()];
+                for (c, chameleon) in self.bitboards[color | Piece::Chameleon].into_iter().enumerate(){
+                    let [a, b] = get_death_squares(to, chameleon);
+                    if bits[2*c]{
+                        taken |= a;
+                    }
+                    if bits[2*c + 1]{
+                        taken |= b;
+                    }
+                }
+            },
+            Piece::Springer => {
+                if m.get_c1_piece() != Piece::Empty{
+                    taken |= get_springer_captured_square(from, to);
+                }
+                for s in chain_squares(from, to, m.get_springer_chain()){
+                    taken |= Bitboard::from(s);
+                }
+            },
+            Piece::Chameleon => {
+                let maybe_stradler_captures = 
+                    get_potential_stradler_captures(to, self.bitboards[color | Piece::Stradler] | self.bitboards[color | Piece::Chameleon]);
+                let bits = [m.get_chameleon_c1_bit(), m.get_chameleon_c2_bit(), m.get_chameleon_c3_bit(), m.get_chameleon_c4_bit()];
+                for (bit, s) in bits.into_iter().zip(maybe_stradler_captures){
+                    if bit{
+                        taken |= s;
+                    }
+                }
+
+                let [a, b] = get_death_squares(to, king_square);
+                if m.get_chameleon_c5_bit(){
+                    taken |= a;
+                }
+                if m.get_chameleon_c6_bit(){
+                    taken |= b;
+                }
+
+                if m.get_chameleon_c7_bit(){
+                    taken |= Bitboard::from(to);
+                }
+
+                if !coordinator.is_empty(){
+                    let [a, b] = get_death_squares(to, coordinator.bitscanforward_square());
+                    if m.get_chameleon_c8_bit(){
+                        taken |= a;
+                    }
+                    if m.get_chameleon_c9_bit(){
+                        taken |= b;
+                    }
+                }
+
+                if m.get_chameleon_c10_bit(){
+                    taken |= get_retractor_lookup(from, to);
+                }
+
+                if m.get_chameleon_c11_bit(){
+                    taken |= get_springer_captured_square(from, to);
+                }
+                for s in chain_squares(from, to, m.get_chameleon_chain()){
+                    taken |= Bitboard::from(s);
+                }
+            },
+            Piece::Retractor => {
+                if m.get_c1_piece() != Piece::Empty{
+                    taken |= get_retractor_lookup(from, to);
+                }
+            },
+            Piece::King => {
+                if m.get_c1_piece() != Piece::Empty{
+                    taken |= Bitboard::from(to);
+                }
+
+                if !coordinator.is_empty(){
+                    let [a, b] = get_death_squares(to, coordinator.bitscanforward_square());
+                    if m.get_c2_piece() != Piece::Empty{
+                        taken |= a;
+                    }
+                    if m.get_c3_piece() != Piece::Empty{
+                        taken |= b;
+                    }
+                }
+
+                let bits = [m.get_c5_bit(), m.get_c6_bit(), m.get_c7_bit(), m.get_c8_bit()];
+                for (c, chameleon) in self.bitboards[color | Piece::Chameleon].into_iter().enumerate(){
+                    let [a, b] = get_death_squares(to, chameleon);
+                    if bits[2*c]{
+                        taken |= a;
+                    }
+                    if bits[2*c + 1]{
+                        taken |= b;
+                    }
+                }
+            },
+        }
+
+        taken
+    }
+
+    /// with FILTERED false the filter is taken to be GenFilter::ALL, so the
+    /// checks on it fold away and generate_moves pays nothing for them
+    fn push_moves<S: MoveSink + ?Sized, const FILTERED: bool>(&self, sink: &mut S, filter: &GenFilter) -> ControlFlow<()>{
+
+        let filter = if FILTERED {filter} else {&GenFilter::ALL};
+
+        if !filter.captures && !filter.quiet{
+            return ControlFlow::Continue(());
+        }
+
+        let not_to_play = !self.to_play;
+        let king_square = self.bitboards[self.to_play | Piece::King].bitscanforward_square();
+        
+        // the immobilizer's own freeze by a chameleon is in here too
+        let immobilized = self.frozen(self.to_play);
+
         let total_board: Bitboard = self.bitboards[Color::White] | self.bitboards[Color::Black];
 
         /*
             STRADLER MOVES
         */
-        let mut stradlers: Bitboard = self.bitboards[self.to_play | Piece::Stradler] &! immobilized;
+        let stradlers: Bitboard = self.bitboards[self.to_play | Piece::Stradler] &! immobilized & filter.movers(Piece::Stradler);
 
-        while !stradlers.is_empty(){
-
-            let from: Square = stradlers.pop_lsb_square();
-            
-            let mut move_bitboard: Bitboard = get_orth_moves(from, total_board) &! total_board;
+        for from in stradlers{
 
-            while !move_bitboard.is_empty(){
+            let move_bitboard: Bitboard = get_orth_moves(from, total_board) &! total_board & filter.targets;
 
-                let to: Square = move_bitboard.pop_lsb_square();
+            for to in move_bitboard{
 
                 let maybe_captures: [Bitboard; 4] = 
                     get_potential_stradler_captures(to, self.bitboards[self.to_play | Piece::Stradler] & !Bitboard::from(from));
@@ -208,11 +907,11 @@ impl Position{
                 if !(maybe_chameleon_capture[1] & self.bitboards[not_to_play | Piece::Stradler]).is_empty(){
                     m.set_c2_piece(Piece::Stradler);
                 }
-                // left
+                // down
                 if !(maybe_chameleon_capture[2] & self.bitboards[not_to_play | Piece::Stradler]).is_empty(){
                     m.set_c3_piece(Piece::Stradler);
                 }
-                // down
+                // left
                 if !(maybe_chameleon_capture[3] & self.bitboards[not_to_play | Piece::Stradler]).is_empty(){
                     m.set_c4_piece(Piece::Stradler);
                 }
@@ -221,7 +920,9 @@ impl Position{
                 m.set_to(to);
                 m.set_piece(Piece::Stradler);
 
-                move_list.add_move(m);
+                if filter.allows(m){
+                    sink.push(m)?;
+                }
 
             }
 
@@ -232,17 +933,15 @@ impl Position{
         /*
             COORDINATOR MOVES
          */
-        let mut coordinators: Bitboard = self.bitboards[self.to_play | Piece::Coordinator] &! immobilized;
+        let mut coordinators: Bitboard = self.bitboards[self.to_play | Piece::Coordinator] &! immobilized & filter.movers(Piece::Coordinator);
 
         // maximum of one coordinator on the board
         if !coordinators.is_empty(){
             let from = coordinators.pop_lsb_square();
 
-            let mut move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board;
+            let move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board & filter.targets;
             
-            while !move_bitboard.is_empty(){
-
-                let to = move_bitboard.pop_lsb_square();
+            for to in move_bitboard{
 
                 let mut m = Move::EMPTY;
 
@@ -257,13 +956,17 @@ impl Position{
                     m.set_c2_piece(self.board[coord_kind_death[1].bitscanforward()]);
                 }
 
+                // no need for the chameleon death squares once a quiet move is known to capture
+                if !filter.captures && m.is_capture(){
+                    continue;
+                }
+
                 // coordinator chameleon death squares (only captures king)
                 let mut coord_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
-                let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+                let chameleons = self.bitboards[self.to_play | Piece::Chameleon];
 
                 let mut i: usize = 0;
-                while !chameleons.is_empty(){
-                    let c = chameleons.pop_lsb_square();
+                for c in chameleons{
                     let d = get_death_squares(to, c);
                     coord_chameleon_death[i] = d[0];
                     coord_chameleon_death[i+1] = d[1];
@@ -288,7 +991,9 @@ impl Position{
                 m.set_to(to);
                 m.set_piece(Piece::Coordinator);
 
-                move_list.add_move(m);
+                if filter.allows(m){
+                    sink.push(m)?;
+                }
 
             }
 
@@ -299,58 +1004,74 @@ impl Position{
         /*
             SPRINGER MOVES
          */
-        let mut springers = self.bitboards[self.to_play | Piece::Springer] &! immobilized;
-
-        while !springers.is_empty(){
-            let from = springers.pop_lsb_square();
+        let springers = self.bitboards[self.to_play | Piece::Springer] &! immobilized & filter.movers(Piece::Springer);
 
+        for from in springers{
             let mut move_bitboard = get_orth_moves(from, total_board) | get_diag_moves(from, total_board);
-            let mut maybe_captures = move_bitboard & self.bitboards[not_to_play];
+            let maybe_captures = if filter.captures {move_bitboard & self.bitboards[not_to_play]} else {Bitboard::EMPTY};
             move_bitboard &= !total_board;
 
-            // just moves
-            while !move_bitboard.is_empty(){
-                let to = move_bitboard.pop_lsb_square();
+            if !filter.quiet{
+                move_bitboard = Bitboard::EMPTY;
+            }
+            move_bitboard &= filter.targets;
 
+            // just moves
+            for to in move_bitboard{
                 let mut m = Move::EMPTY;
 
                 m.set_from(from);
                 m.set_to(to);
                 m.set_piece(Piece::Springer);
 
-                move_list.add_move(m);
+                sink.push(m)?;
             }
 
             // just captures
-            while !maybe_captures.is_empty(){
-                let capturing = maybe_captures.pop_lsb_square();
-                let landing = get_springer_landing_square(from, capturing);
-
-                if !(landing &! total_board).is_empty(){
+            for capturing in maybe_captures{
+                self.springer_leaps(from, capturing, self.bitboards[not_to_play], total_board, |landing, jumped|{
                     let mut m = Move::EMPTY;
 
                     m.set_from(from);
-                    m.set_to(landing.bitscanforward_square());
+                    m.set_to(landing);
                     m.set_piece(Piece::Springer);
-                    m.set_c1_piece(self.board[capturing]);
 
-                    move_list.add_move(m);
-                }
+                    let (last, earlier) = jumped.split_last().unwrap();
+                    m.set_c1_piece(self.board[*last]);
+                    for s in earlier.iter().rev(){
+                        m.add_springer_capture(from.distance(*s) as u32, self.board[*s]);
+                    }
+
+                    if filter.allows(m) {sink.push(m)} else {ControlFlow::Continue(())}
+                })?;
             }
 
         }
 
-        /* 
-            chameleon moves (skipping for now)
+        /*
+            CHAMELEON MOVES
+
+            a chameleon captures each piece the way that piece captures, and
+            only that piece that way:
+                stradler     moving orthogonally to pin it against a friendly stradler or chameleon
+                coordinator  on a death square of the chameleon and the friendly king
+                king         stepping onto it, or one step to put it on a death square with the friendly coordinator
+                retractor    stepping directly away from it
+                springer     leaping over it, chains only with rules.springer_multi_capture
+                immobilizer  can't be captured, the chameleon immobilizes it instead
+                             when rules.chameleon_immobilizes_immobilizer is set
+                chameleon    can't be captured or immobilized by a chameleon
+
+            a friendly chameleon also stands in for a stradler when a stradler
+            pins a stradler, for the coordinator when the king captures a
+            coordinator and for the king when the coordinator captures a king
         */
-        let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon] &! immobilized;
-        while !chameleons.is_empty(){
-            let from: Square = chameleons.pop_lsb_square();
-
+        let chameleons = self.bitboards[self.to_play | Piece::Chameleon] &! immobilized & filter.movers(Piece::Chameleon);
+        for from in chameleons{
             let mut move_bitboard = get_orth_moves(from, total_board) | get_diag_moves(from, total_board);
 
             // used for checking potential chamleon springer captures
-            let mut maybe_springer_captures = move_bitboard & self.bitboards[not_to_play | Piece::Springer];
+            let maybe_springer_captures = if filter.captures {move_bitboard & self.bitboards[not_to_play | Piece::Springer]} else {Bitboard::EMPTY};
 
             // used for checking when moves may be stradler captures
             let orth_moves: Bitboard = get_orth_moves(from, total_board);
@@ -361,8 +1082,13 @@ impl Position{
             // total ^ (self.bitboards[not_to_play | Piece::King] & king_mask)
             move_bitboard &= !(total_board &! (self.bitboards[not_to_play | Piece::King] & king_mask));
 
-            while !move_bitboard.is_empty(){
-                let to: Square = move_bitboard.pop_lsb_square();
+            // stepping onto the king always takes it
+            if !filter.captures{
+                move_bitboard &= !total_board;
+            }
+            move_bitboard &= filter.targets;
+
+            for to in move_bitboard{
                 let bitboard_to = Bitboard::from(to);
                 let mut m: Move = Move::EMPTY;
 
@@ -425,7 +1151,12 @@ impl Position{
                     }
                 }
 
-                // everything else (so just the coordinator i guess???)
+                // no need for the death squares once a quiet move is known to capture
+                if !filter.captures && m.is_capture(){
+                    continue;
+                }
+
+                // coordinator
                 let cham_coord_king_death: [Bitboard; 2] = get_death_squares(to, king_square);
 
                 if !(cham_coord_king_death[0] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
@@ -435,23 +1166,26 @@ impl Position{
                     m.set_chameleon_c6_bit(true);
                 }
 
-                move_list.add_move(m);
+                if filter.allows(m){
+                    sink.push(m)?;
+                }
             }
 
-            while !maybe_springer_captures.is_empty(){
-                let capturing: Square = maybe_springer_captures.pop_lsb_square();
-                let landing = get_springer_landing_square(from, capturing);
-                
-                if !(landing &! total_board).is_empty(){
+            for capturing in maybe_springer_captures{
+                self.springer_leaps(from, capturing, self.bitboards[not_to_play | Piece::Springer], total_board, |landing, jumped|{
                     let mut m = Move::EMPTY;
 
                     m.set_from(from);
-                    m.set_to(landing.bitscanforward_square());
+                    m.set_to(landing);
                     m.set_piece(Piece::Chameleon);
                     m.set_chameleon_c11_bit(true);
 
-                    move_list.add_move(m);
-                }
+                    for s in &jumped[..jumped.len()-1]{
+                        m.add_chameleon_springer_capture(from.distance(*s) as u32);
+                    }
+
+                    if filter.allows(m) {sink.push(m)} else {ControlFlow::Continue(())}
+                })?;
             }
 
         }
@@ -459,23 +1193,26 @@ impl Position{
         /*
             RETRACTOR MOVES
          */
-        let mut retractor = self.bitboards[self.to_play | Piece::Retractor] &! immobilized;
+        let mut retractor = self.bitboards[self.to_play | Piece::Retractor] &! immobilized & filter.movers(Piece::Retractor);
 
         // only one retractor
         if !retractor.is_empty(){
 
             let from: Square = retractor.pop_lsb_square();
 
-            let mut move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board;
+            let mut move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board & filter.targets;
 
             // filter out moves that might be captures
-            let mut maybe_captures = move_bitboard & get_king_moves(from);
+            let maybe_captures = move_bitboard & get_king_moves(from);
 
             move_bitboard &= !maybe_captures;
 
-            while !move_bitboard.is_empty(){
+            // the rest never capture
+            if !filter.quiet{
+                move_bitboard = Bitboard::EMPTY;
+            }
 
-                let to: Square = move_bitboard.pop_lsb_square();
+            for to in move_bitboard{
 
                 let mut m = Move::EMPTY;
 
@@ -483,12 +1220,10 @@ impl Position{
                 m.set_to(to);
                 m.set_piece(Piece::Retractor);
 
-                move_list.add_move(m);
+                sink.push(m)?;
             }
 
-            while !maybe_captures.is_empty(){
-
-                let to: Square = maybe_captures.pop_lsb_square();
+            for to in maybe_captures{
 
                 let mut m = Move::EMPTY;
 
@@ -502,7 +1237,9 @@ impl Position{
                     m.set_c1_piece(self.board[capturing.bitscanforward()]);
                 }
 
-                move_list.add_move(m);
+                if filter.allows(m){
+                    sink.push(m)?;
+                }
             }
 
         }
@@ -511,51 +1248,47 @@ impl Position{
             IMMBOLIZER MOVES
         */
         // immobilizor can't capture
-        let chameleon_immobilzed: Bitboard = {
-            let mut chameleons = self.bitboards[not_to_play | Piece::Chameleon];
-            let mut result = Bitboard(0);
-            while !chameleons.is_empty(){
-                result |= get_king_moves(chameleons.pop_lsb_square());
-            }
-            result
-        };
-
-        let mut immobilizor = self.bitboards[self.to_play | Piece::Immobilizer] & !(immobilized | chameleon_immobilzed);
+        let mut immobilizor = self.bitboards[self.to_play | Piece::Immobilizer] & !immobilized & filter.movers(Piece::Immobilizer);
         
+        // it only has quiet moves
+        if !filter.quiet{
+            immobilizor = Bitboard::EMPTY;
+        }
 
         if !immobilizor.is_empty(){
 
             let from = immobilizor.pop_lsb_square();
 
-            let mut move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board;
-
-            while !move_bitboard.is_empty(){
-                let to = move_bitboard.pop_lsb_square();
+            let move_bitboard = (get_orth_moves(from, total_board) | get_diag_moves(from, total_board)) &! total_board & filter.targets;
 
+            for to in move_bitboard{
                 let mut m = Move::EMPTY;
 
                 m.set_from(from);
                 m.set_to(to);
                 m.set_piece(Piece::Immobilizer);
 
-                move_list.add_move(m);
+                sink.push(m)?;
             }
         }
 
         /*
             KING MOVES
          */
-        let mut king: Bitboard = self.bitboards[self.to_play | Piece::King] &! immobilized;
+        let mut king: Bitboard = self.bitboards[self.to_play | Piece::King] &! immobilized & filter.movers(Piece::King);
 
         if !king.is_empty(){
             //let coord_square = self.bitboards[self.to_play | Piece::Coordinator].bitscanforward_square();
             let from = king.pop_lsb_square();
 
-            let mut move_bitboard = get_king_moves(from) &! self.bitboards[self.to_play];
+            let mut move_bitboard = get_king_moves(from) &! self.bitboards[self.to_play] & filter.targets;
 
-            while !move_bitboard.is_empty(){
+            // stepping onto a piece always takes it
+            if !filter.captures{
+                move_bitboard &= !total_board;
+            }
 
-                let to = move_bitboard.pop_lsb_square();
+            for to in move_bitboard{
 
                 let mut m = Move::EMPTY;
 
@@ -566,65 +1299,364 @@ impl Position{
                     get_death_squares(to, self.bitboards[self.to_play | Piece::Coordinator].bitscanforward_square())
                 };
 
-                if !(king_coord_death[0] & self.bitboards[not_to_play]).is_empty(){
-                    m.set_c2_piece(self.board[king_coord_death[0].bitscanforward_square()]);
-                }
-                if !(king_coord_death[1] & self.bitboards[not_to_play]).is_empty(){
-                    m.set_c3_piece(self.board[king_coord_death[1].bitscanforward_square()]);
-                }
+                if !(king_coord_death[0] & self.bitboards[not_to_play]).is_empty(){
+                    m.set_c2_piece(self.board[king_coord_death[0].bitscanforward_square()]);
+                }
+                if !(king_coord_death[1] & self.bitboards[not_to_play]).is_empty(){
+                    m.set_c3_piece(self.board[king_coord_death[1].bitscanforward_square()]);
+                }
+
+                // no need for the chameleon death squares once a quiet move is known to capture
+                if !filter.captures && m.is_capture(){
+                    continue;
+                }
+
+                // king chameleon captures (only captures coordinator)
+                let mut king_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
+                let chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+
+                let mut i: usize = 0;
+                for c in chameleons{
+                    let d = get_death_squares(to, c);
+                    king_chameleon_death[i] = d[0];
+                    king_chameleon_death[i+1] = d[1];
+                    i += 2;
+                }
+                
+                if !(king_chameleon_death[0] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
+                    m.set_c5_bit(true);
+                }
+                if !(king_chameleon_death[1] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
+                    m.set_c6_bit(true);
+                }
+                if !(king_chameleon_death[2] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
+                    m.set_c7_bit(true);
+                }
+                if !(king_chameleon_death[3] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
+                    m.set_c8_bit(true);
+                }
+
+
+                m.set_from(from);
+                m.set_to(to);
+                m.set_piece(Piece::King);
+                
+                // capture by displacement
+                //m.set_c1_piece(self.board[to]);
+                if !(Bitboard::from(to) & self.bitboards[not_to_play]).is_empty(){
+                    m.set_c1_piece(self.board[to]);
+                }
+
+                if filter.allows(m){
+                    sink.push(m)?;
+                }
+
+            }
+
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Calls f with the landing square and the squares jumped over, nearest
+    /// to from first, for every leap of a springer on from that starts by
+    /// taking the piece on first
+    ///
+    /// only pieces in capturable are taken, and only one per leap unless
+    /// the rules allow more
+    fn springer_leaps<F>(&self, from: Square, first: Square, capturable: Bitboard, total_board: Bitboard, mut f: F) -> ControlFlow<()>
+    where
+        F: FnMut(Square, &[Square]) -> ControlFlow<()>,
+    {
+        let mut jumped: [Square; 3] = [first; 3];
+        let mut count: usize = 0;
+        let mut capturing = first;
+
+        loop{
+            let landing = get_springer_landing_square(from, capturing);
+
+            if !(landing & total_board).is_empty() || landing.is_empty(){
+                return ControlFlow::Continue(());
+            }
+
+            jumped[count] = capturing;
+            count += 1;
+
+            let landing = landing.bitscanforward_square();
+            f(landing, &jumped[..count])?;
+
+            // three captures take up seven squares of the line, there is no room for a fourth
+            if !self.rules.springer_multi_capture || count == 3{
+                return ControlFlow::Continue(());
+            }
+
+            // carry on to the next piece on the line
+            let mut next = get_springer_landing_square(from, landing);
+            while !next.is_empty() && (next & total_board).is_empty(){
+                next = get_springer_landing_square(from, next.bitscanforward_square());
+            }
+
+            if (next & capturable).is_empty(){
+                return ControlFlow::Continue(());
+            }
+            capturing = next.bitscanforward_square();
+        }
+    }
+
+    /// Returns every move that could have led to the current position
+    ///
+    /// the moves are for the side that just moved, and unmake_move on
+    /// one of them gives a position where it is psuedolegal and
+    /// make_move returns to the current position. a captured piece can
+    /// be anything its side has room for (see MAX_COUNT), so the same
+    /// from and to squares can appear once for every uncapture
+    pub fn generate_unmoves(&self) -> Vec<Move>{
+        self.unmoves(true)
+    }
+
+    /// unmoves that don't uncapture anything
+    pub(crate) fn generate_quiet_unmoves(&self) -> Vec<Move>{
+        self.unmoves(false)
+    }
+
+    fn unmoves(&self, uncaptures: bool) -> Vec<Move>{
+
+        let mut result = Vec::new();
+        let mover = !self.to_play;
+
+        // generate_moves needs a king to run in the position before
+        if self.bitboards[mover | Piece::King].is_empty(){
+            return result;
+        }
+
+        let total_board: Bitboard = self.bitboards[Color::White] | self.bitboards[Color::Black];
+
+        for piece in &(Piece::ALL)[1..]{
+
+            let pieces = self.bitboards[mover | *piece];
+
+            for to in pieces{
+                // every move ends with a slide or a king step onto an empty square
+                let mut froms = (get_orth_moves(to, total_board) | get_diag_moves(to, total_board)) & !total_board;
+                if *piece == Piece::King{
+                    froms &= get_king_moves(to);
+                }
+
+                for from in froms{
+                    let slots = if uncaptures {self.uncaptures(from, to, *piece)} else {Vec::new()};
+                    self.add_unmoves(from, to, *piece, &slots, &mut result);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// squares where piece moving from -> to could have captured,
+    /// following the same rules generate_moves does
+    fn uncaptures(&self, from: Square, to: Square, piece: Piece) -> Vec<Uncapture>{
+
+        let mover = !self.to_play;
+        let total_board: Bitboard = self.bitboards[Color::White] | self.bitboards[Color::Black];
+
+        let king_square = self.bitboards[mover | Piece::King].bitscanforward_square();
+        let coordinator = self.bitboards[mover | Piece::Coordinator];
+        let chameleons = self.bitboards[mover | Piece::Chameleon];
+        let king_adjacent = !(get_king_moves(from) & Bitboard::from(to)).is_empty();
+
+        let mut result: Vec<Uncapture> = Vec::new();
+
+        // a captured piece needs an empty square to come back to,
+        // or the square the capturing piece leaves
+        let mut add = |squares: Bitboard, pieces: &'static [Piece], record: fn(&mut Move, Piece)|{
+            if squares.is_empty(){
+                return;
+            }
+            let square = squares.bitscanforward_square();
+            if square == to || (square != from && (total_board & Bitboard::from(square)).is_empty()){
+                result.push(Uncapture{ square, pieces, record });
+            }
+        };
+
+        let death_squares_with = |other: Bitboard| -> [Bitboard; 2]{
+            if other.is_empty() {[Bitboard::EMPTY; 2]} else {get_death_squares(to, other.bitscanforward_square())}
+        };
+
+        let chameleon_death_squares = ||{
+            let mut result: [Bitboard; 4] = [Bitboard::EMPTY; 4];
+            let mut chameleons = chameleons;
+            let mut i: usize = 0;
+            while !chameleons.is_empty(){
+                let d = get_death_squares(to, chameleons.pop_lsb_square());
+                result[i] = d[0];
+                result[i+1] = d[1];
+                i += 2;
+            }
+            result
+        };
+
+        match piece{
+            Piece::Empty => unreachable!(),
+            Piece::Stradler => {
+                let buddies = get_potential_stradler_captures(to, self.bitboards[mover | Piece::Stradler] & !Bitboard::from(to));
+                let chameleon_buddies = get_potential_stradler_captures(to, chameleons);
+                let records: [fn(&mut Move, Piece); 4] = [Move::set_c1_piece, Move::set_c2_piece, Move::set_c3_piece, Move::set_c4_piece];
+
+                for i in 0..4{
+                    if !buddies[i].is_empty(){
+                        add(buddies[i], ANY_PIECE, records[i]);
+                    }
+                    else{
+                        add(chameleon_buddies[i], &[Piece::Stradler], records[i]);
+                    }
+                }
+            },
+            Piece::Coordinator => {
+                let death = get_death_squares(to, king_square);
+                add(death[0], ANY_PIECE, Move::set_c1_piece);
+                add(death[1], ANY_PIECE, Move::set_c2_piece);
+
+                let death = chameleon_death_squares();
+                add(death[0], &[Piece::King], |m, _| m.set_c5_bit(true));
+                add(death[1], &[Piece::King], |m, _| m.set_c6_bit(true));
+                add(death[2], &[Piece::King], |m, _| m.set_c7_bit(true));
+                add(death[3], &[Piece::King], |m, _| m.set_c8_bit(true));
+            },
+            Piece::Springer => {
+                add(get_springer_captured_square(from, to), ANY_PIECE, Move::set_c1_piece);
+
+                if self.rules.springer_multi_capture{
+                    for (square, step) in chain_steps(from, to){
+                        add(Bitboard::from(square), ANY_PIECE, SPRINGER_CHAIN_RECORDS[step - 1]);
+                    }
+                }
+            },
+            Piece::Chameleon => {
+                let buddies = get_potential_stradler_captures(to, (self.bitboards[mover | Piece::Stradler] | chameleons) & !Bitboard::from(to));
+                if !(get_orth_moves(from, total_board) & Bitboard::from(to)).is_empty(){
+                    add(buddies[0], &[Piece::Stradler], |m, _| m.set_chameleon_c1_bit(true));
+                    add(buddies[1], &[Piece::Stradler], |m, _| m.set_chameleon_c2_bit(true));
+                    add(buddies[2], &[Piece::Stradler], |m, _| m.set_chameleon_c3_bit(true));
+                    add(buddies[3], &[Piece::Stradler], |m, _| m.set_chameleon_c4_bit(true));
+                }
+
+                let death = get_death_squares(to, king_square);
+                add(death[0], &[Piece::Coordinator], |m, _| m.set_chameleon_c5_bit(true));
+                add(death[1], &[Piece::Coordinator], |m, _| m.set_chameleon_c6_bit(true));
+
+                if king_adjacent{
+                    add(Bitboard::from(to), &[Piece::King], |m, _| m.set_chameleon_c7_bit(true));
+
+                    let death = death_squares_with(coordinator);
+                    add(death[0], &[Piece::King], |m, _| m.set_chameleon_c8_bit(true));
+                    add(death[1], &[Piece::King], |m, _| m.set_chameleon_c9_bit(true));
+
+                    add(get_retractor_lookup(from, to), &[Piece::Retractor], |m, _| m.set_chameleon_c10_bit(true));
+                }
+
+                add(get_springer_captured_square(from, to), &[Piece::Springer], |m, _| m.set_chameleon_c11_bit(true));
+
+                if self.rules.springer_multi_capture{
+                    for (square, step) in chain_steps(from, to){
+                        add(Bitboard::from(square), &[Piece::Springer], CHAMELEON_CHAIN_RECORDS[step - 1]);
+                    }
+                }
+            },
+            Piece::Retractor => {
+                if king_adjacent{
+                    add(get_retractor_lookup(from, to), ANY_PIECE, Move::set_c1_piece);
+                }
+            },
+            Piece::Immobilizer => {},
+            Piece::King => {
+                add(Bitboard::from(to), ANY_PIECE, Move::set_c1_piece);
+
+                let death = death_squares_with(coordinator);
+                add(death[0], ANY_PIECE, Move::set_c2_piece);
+                add(death[1], ANY_PIECE, Move::set_c3_piece);
+
+                let death = chameleon_death_squares();
+                add(death[0], &[Piece::Coordinator], |m, _| m.set_c5_bit(true));
+                add(death[1], &[Piece::Coordinator], |m, _| m.set_c6_bit(true));
+                add(death[2], &[Piece::Coordinator], |m, _| m.set_c7_bit(true));
+                add(death[3], &[Piece::Coordinator], |m, _| m.set_c8_bit(true));
+            },
+        }
+
+        result
+    }
+
+    /// tries every combination of uncaptures for piece moving from -> to,
+    /// keeping the ones generate_moves plays in the position before
+    fn add_unmoves(&self, from: Square, to: Square, piece: Piece, slots: &[Uncapture], result: &mut Vec<Move>){
+
+        let victim = self.to_play;
+
+        // choice[i] is 0 for no capture, otherwise 1 + index into slots[i].pieces
+        let mut choice: Vec<usize> = vec![0; slots.len()];
+
+        'combinations: loop{
+
+            let mut m = Move::EMPTY;
+            m.set_from(from);
+            m.set_to(to);
+            m.set_piece(piece);
 
-                // king chameleon captures (only captures coordinator)
-                let mut king_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
-                let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+            let mut returned: [Piece; 64] = [Piece::Empty; 64];
+            let mut counts: [u32; 8] = [0; 8];
+            let mut taken = Bitboard::EMPTY;
 
-                let mut i: usize = 0;
-                while !chameleons.is_empty(){
-                    let c = chameleons.pop_lsb_square();
-                    let d = get_death_squares(to, c);
-                    king_chameleon_death[i] = d[0];
-                    king_chameleon_death[i+1] = d[1];
-                    i += 2;
-                }
-                
-                if !(king_chameleon_death[0] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
-                    m.set_c5_bit(true);
-                }
-                if !(king_chameleon_death[1] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
-                    m.set_c6_bit(true);
+            for (slot, c) in slots.iter().zip(&choice){
+                if *c == 0{
+                    continue;
                 }
-                if !(king_chameleon_death[2] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
-                    m.set_c7_bit(true);
+                let captured = slot.pieces[*c - 1];
+
+                // two captures on one square have to agree on what was there
+                if returned[slot.square] == Piece::Empty{
+                    returned[slot.square] = captured;
+                    counts[captured as usize] += 1;
                 }
-                if !(king_chameleon_death[3] & self.bitboards[not_to_play | Piece::Coordinator]).is_empty(){
-                    m.set_c8_bit(true);
+                else if returned[slot.square] != captured{
+                    counts[0] = 1;
                 }
+                taken |= Bitboard::from(slot.square);
+            }
 
+            // a springer leaves an empty square behind every piece it takes,
+            // so none of them are next to each other. that also keeps the
+            // earlier captures within the slots a move has for them
+            let spread = piece != Piece::Springer || taken.into_iter().all(|s| (get_king_moves(s) & taken).is_empty());
 
-                m.set_from(from);
-                m.set_to(to);
-                m.set_piece(Piece::King);
-                
-                // capture by displacement
-                //m.set_c1_piece(self.board[to]);
-                if !(Bitboard::from(to) & self.bitboards[not_to_play]).is_empty(){
-                    m.set_c1_piece(self.board[to]);
+            let possible = spread && counts[0] == 0 && Piece::ALL[1..].iter().all(|p| {
+                self.bitboards[victim | *p].0.count_ones() + counts[*p as usize] <= MAX_COUNT[*p as usize]
+            });
+
+            if possible{
+                for (slot, c) in slots.iter().zip(&choice){
+                    if *c != 0{
+                        (slot.record)(&mut m, slot.pieces[*c - 1]);
+                    }
                 }
 
-                move_list.add_move(m);
+                let mut before = self.clone();
+                before.unmake_move(m);
 
+                if before.generate_moves().iter().any(|g| *g == m){
+                    result.push(m);
+                }
             }
 
+            // next combination
+            for (i, c) in choice.iter_mut().enumerate(){
+                if *c < slots[i].pieces.len(){
+                    *c += 1;
+                    continue 'combinations;
+                }
+                *c = 0;
+            }
+            break;
         }
-
-        move_list
-        /*
-        move_list.into_iter().filter(|m| {
-            self.make_move(*m);
-            let result = !self.is_check();
-            self.unmake_move(*m);
-            result
-        }).collect()
-        */
     }
 
     pub fn make_move(&mut self, m: Move){
@@ -689,11 +1721,10 @@ impl Position{
 
                 // coordinator chameleon death squares (only captures king)
                 let mut coord_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
-                let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+                let chameleons = self.bitboards[self.to_play | Piece::Chameleon];
 
                 let mut i: usize = 0;
-                while !chameleons.is_empty(){
-                    let c = chameleons.pop_lsb_square();
+                for c in chameleons{
                     let d = get_death_squares(to, c);
                     coord_chameleon_death[i] = d[0];
                     coord_chameleon_death[i+1] = d[1];
@@ -720,6 +1751,10 @@ impl Position{
                     self.remove_piece(not_to_play, m.get_c1_piece(), captured_on.bitscanforward_square());
                 }
 
+                // the rest of a leap taking more than one piece
+                for (s, p) in chain_squares(from, to, m.get_springer_chain()).zip([m.get_c2_piece(), m.get_c3_piece()]){
+                    self.remove_piece(not_to_play, p, s);
+                }
             },
             Piece::Chameleon => {
                 
@@ -778,6 +1813,9 @@ impl Position{
                     let captured_on = get_springer_captured_square(from, to);
                     self.remove_piece(not_to_play, Piece::Springer, captured_on.bitscanforward_square());
                 }
+                for s in chain_squares(from, to, m.get_chameleon_chain()){
+                    self.remove_piece(not_to_play, Piece::Springer, s);
+                }
             },
             Piece::Retractor => {
                 if m.get_c1_piece() != Piece::Empty{
@@ -808,11 +1846,10 @@ impl Position{
 
                 // king chameleon captures (only captures coordinator)
                 let mut king_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
-                let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+                let chameleons = self.bitboards[self.to_play | Piece::Chameleon];
 
                 let mut i: usize = 0;
-                while !chameleons.is_empty(){
-                    let c = chameleons.pop_lsb_square();
+                for c in chameleons{
                     let d = get_death_squares(to, c);
                     king_chameleon_death[i] = d[0];
                     king_chameleon_death[i+1] = d[1];
@@ -846,12 +1883,14 @@ impl Position{
         self.board[to] = piece_type;
         self.board[from] = Piece::Empty;
 
+        self.zobrist_hash ^= piece_key(self.to_play, piece_type, from) ^ piece_key(self.to_play, piece_type, to) ^ side_key();
+
         // switch color to play
         self.to_play = !self.to_play;
 
         self.halfmoves += 1;
 
-        debug_assert!(self.is_consistent(), "{}, {}, {}, {:?}", from, to, piece_type, m);
+        debug_assert_eq!(self.inconsistency(), None, "{}, {}, {}, {:?}", from, to, piece_type, m);
     }
 
     pub fn unmake_move(&mut self, m: Move){
@@ -879,9 +1918,10 @@ impl Position{
         self.board[from] = self.board[to];
         self.board[to] = Piece::Empty;
 
-        
+        self.zobrist_hash ^= piece_key(self.to_play, piece_type, from) ^ piece_key(self.to_play, piece_type, to) ^ side_key();
 
-        self.halfmoves -= 1;
+        // unmoving out of a position read with a halfmove count of 0
+        self.halfmoves = self.halfmoves.saturating_sub(1);
 
         //let mut captures = m.get_capture_bits();
         let king_square: Square = self.bitboards[self.to_play | Piece::King].bitscanforward_square();
@@ -964,6 +2004,9 @@ impl Position{
                     self.place_piece(not_to_play, m.get_c1_piece(), captured_on.bitscanforward_square());
                 }
 
+                for (s, p) in chain_squares(from, to, m.get_springer_chain()).zip([m.get_c2_piece(), m.get_c3_piece()]){
+                    self.place_piece(not_to_play, p, s);
+                }
             },
             Piece::Chameleon => {
                 /* */
@@ -1023,6 +2066,9 @@ impl Position{
                     let captured_on = get_springer_captured_square(from, to);
                     self.place_piece(not_to_play, Piece::Springer, captured_on.bitscanforward_square());
                 }
+                for s in chain_squares(from, to, m.get_chameleon_chain()){
+                    self.place_piece(not_to_play, Piece::Springer, s);
+                }
             },
             Piece::Retractor => {
                 if m.get_c1_piece() != Piece::Empty{
@@ -1052,11 +2098,10 @@ impl Position{
                 }
 
                 let mut king_chameleon_death: [Bitboard; 4] = [Bitboard::EMPTY; 4];
-                let mut chameleons = self.bitboards[self.to_play | Piece::Chameleon];
+                let chameleons = self.bitboards[self.to_play | Piece::Chameleon];
 
                 let mut i: usize = 0;
-                while !chameleons.is_empty(){
-                    let c = chameleons.pop_lsb_square();
+                for c in chameleons{
                     let d = get_death_squares(to, c);
                     king_chameleon_death[i] = d[0];
                     king_chameleon_death[i+1] = d[1];
@@ -1095,7 +2140,7 @@ impl Position{
         self.halfmoves -= 1;
         */
 
-        debug_assert!(self.is_consistent(), "{:?}", m);
+        debug_assert_eq!(self.inconsistency(), None, "{:?}", m);
     }
 
     /// returns true if self.to_play is currently attacking enemy king
@@ -1117,6 +2162,17 @@ impl Position{
         is_check
     }
 
+    /// returns true if self.to_play's king is currently attacked
+    pub fn is_check(&mut self) -> bool{
+        self.to_play = !self.to_play;
+        self.zobrist_hash ^= side_key();
+        let result = self.is_attacking_king();
+        self.to_play = !self.to_play;
+        self.zobrist_hash ^= side_key();
+
+        result
+    }
+
     pub fn is_move_legal(&mut self, m: Move) -> bool{
         self.make_move(m);
 
@@ -1131,42 +2187,106 @@ impl Position{
         result
     }
 
-    /// returns true if self.to_play color is currently in checkmate
-    pub fn is_checkmate(&mut self) -> bool{
+    /// Plays m if it is legal in the current position, leaving the
+    /// position untouched otherwise
+    ///
+    /// m has to be one of the moves generate_moves would give, capture bits
+    /// included, so moves made for another position are rejected instead of
+    /// corrupting the board. safe to use on moves from untrusted sources
+    pub fn try_make_move(&mut self, m: Move) -> std::result::Result<Undo, IllegalMove>{
+
+        if !self.generate_moves().into_iter().any(|g| g == m){
+            return Err(IllegalMove{});
+        }
 
-        let current_color: Color = self.to_play;
-        let current_moves: MoveList = self.generate_moves();
+        if !self.is_move_legal(m){
+            return Err(IllegalMove{});
+        }
 
-        current_moves.into_iter().all(|m| {
-            self.make_move(m);
-            //println!("{self}");
+        self.make_move(m);
 
-            let responses = self.generate_moves();
+        Ok(Undo{ m, zobrist_hash: self.zobrist_hash, halfmoves: self.halfmoves, fullmoves: self.fullmoves })
+    }
 
-            let king_is_attacked = responses.into_iter().any(|r| {
-                self.make_move(r);
-                //println!("{self}");
-                let king_gone = self.bitboards[current_color | Piece::King].is_empty();
-                self.unmake_move(r);
-                //println!("{self}");
+    /// Takes back a move played with try_make_move
+    ///
+    /// fails, leaving the position as it is, if the position is not the one
+    /// the move led to. moves made since have to be undone first
+    pub fn undo_move(&mut self, undo: Undo) -> std::result::Result<(), IllegalMove>{
 
-                king_gone
-            });
+        // the counters tell apart a repetition of the position
+        if self.zobrist_hash != undo.zobrist_hash || self.halfmoves != undo.halfmoves || self.fullmoves != undo.fullmoves{
+            return Err(IllegalMove{});
+        }
 
-            //println!("{self}");
-            self.unmake_move(m);
-            //println!("{self}");
+        self.unmake_move(undo.m);
 
-            king_is_attacked
-        })
+        Ok(())
+    }
+
+    /// returns true if self.to_play color is currently in checkmate
+    ///
+    /// every move leaving the king capturable counts, so this includes stalemate
+    pub fn is_checkmate(&mut self) -> bool{
+        !self.has_legal_move()
     }
     
+    /// Checks the position is one the rest of the library can work with
+    ///
+    /// both sides need exactly one king, no more pieces than generate_moves
+    /// can handle (see MAX_COUNT) and the side that just moved can't have
+    /// left its king capturable. the first two are checked before looking
+    /// at captures, as generate_moves relies on them
+    pub fn validate(&self) -> std::result::Result<(), Vec<PositionIssue>>{
+
+        let mut issues = Vec::new();
+
+        if !self.is_consistent(){
+            issues.push(PositionIssue::Inconsistent);
+        }
+
+        for c in [Color::White, Color::Black]{
+            for p in &(Piece::ALL)[1..]{
+                let count = self.bitboards[c | *p].0.count_ones();
+                let symbol = Piece::PIECE_SYMBOLS[c | *p];
+
+                if *p == Piece::King && count != 1{
+                    issues.push(PositionIssue::KingCount(symbol, count));
+                }
+                else if count > MAX_COUNT[*p as usize]{
+                    issues.push(PositionIssue::TooMany(symbol, count));
+                }
+            }
+        }
+
+        if issues.is_empty() && self.clone().is_attacking_king(){
+            issues.push(PositionIssue::KingCapturable(Piece::PIECE_SYMBOLS[!self.to_play | Piece::King]));
+        }
+
+        if issues.is_empty(){
+            Ok(())
+        }
+        else{
+            Err(issues)
+        }
+    }
+
     /// checks if internal state is consistent
     /// 
     /// internal state is consistent if self.bitboards and self.board agree on current position
+    /// and the incrementally updated hash matches one computed from scratch
     /// 
-    /// used for debugging purposes
-    fn is_consistent(&self) -> bool{
+    /// used for debugging purposes and by validate
+    pub(crate) fn is_consistent(&self) -> bool{
+        self.inconsistency().is_none()
+    }
+
+    /// what makes the internal state inconsistent, if anything
+    pub(crate) fn inconsistency(&self) -> Option<String>{
+
+        if self.zobrist_hash != self.compute_zobrist_hash(){
+            return Some(format!("zobrist hash does not match position\n{self}"));
+        }
 
         let mut board_from_bitboard: [Piece; 64] = [Piece::Empty; 64];
 
@@ -1174,15 +2294,11 @@ impl Position{
 
             for p in &(Piece::ALL)[1..]{
 
-                let mut piece_bb = self.bitboards[c | *p];
-
-                while !piece_bb.is_empty(){
-                    let square = piece_bb.pop_lsb_square();
+                let piece_bb = self.bitboards[c | *p];
 
+                for square in piece_bb{
                     if board_from_bitboard[square] != Piece::Empty{
-                        eprintln!("self.bitboards has overlapping pieces on {square:?}");
-                        eprintln!("{:?} and {:?}", *p, board_from_bitboard[square]);
-                        return false;
+                        return Some(format!("self.bitboards has overlapping pieces on {square:?}\n{:?} and {:?}", *p, board_from_bitboard[square]));
                     }
                     board_from_bitboard[square] = *p;       
                 }
@@ -1192,16 +2308,12 @@ impl Position{
         for s in Square::ALL{
 
             if board_from_bitboard[s] != self.board[s]{
-                eprintln!("self.board and self.bitboards disagree on board state at {s}");
-                eprintln!("{self}");
-                eprintln!("{:?}, {:?}", self.board[s], board_from_bitboard[s]);
-
-                return false;
+                return Some(format!("self.board and self.bitboards disagree on board state at {s}\n{self}\n{:?}, {:?}", self.board[s], board_from_bitboard[s]));
             }
 
         }
 
-        true
+        None
     }
 }
 impl fmt::Display for Position{
@@ -1273,12 +2385,596 @@ mod test{
 
     }
 
+    #[test]
+    fn fen_round_trip_test(){
+
+        let fens = [
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+            "k5R1/p7/8/8/7K/8/8/5B2 w 0 0",
+            "un1q1kb1/pp1ppppn/P2PPP1p/4RB1b/8/2p4P/1PPK2P1/1NBQ2NU b 0 16",
+        ];
+
+        for fen in fens{
+            assert_eq!(Position::from_FEN(fen).unwrap().to_FEN(), fen);
+        }
+    }
+
+    #[test]
+    fn parse_move_test(){
+
+        let position = Position::from_start_position();
+
+        let m = position.parse_move("e2e4").unwrap();
+        assert_eq!(m.get_from(), Square::E2);
+        assert_eq!(m.get_to(), Square::E4);
+        assert_eq!(position.parse_move("E2E4"), Some(m));
+
+        // stradlers move like rooks, so e2e3 is fine but a knight jump is not
+        assert!(position.parse_move("e2e3").is_some());
+        assert!(position.parse_move("b1c3").is_none());
+        assert!(position.parse_move("e2").is_none());
+    }
+
+    #[test]
+    fn zobrist_hash_test(){
+
+        let mut position = Position::from_start_position();
+        let start_hash = position.zobrist_hash();
+
+        // transposition: the hash only depends on the resulting position
+        let moves = ["e2e4", "e7e5", "d2d4", "d7d5"];
+        let mut played = Vec::new();
+        for notation in moves{
+            let m = position.parse_move(notation).unwrap();
+            position.make_move(m);
+            played.push(m);
+        }
+
+        let mut transposed = Position::from_start_position();
+        for notation in ["d2d4", "d7d5", "e2e4", "e7e5"]{
+            transposed.make_move(transposed.parse_move(notation).unwrap());
+        }
+        assert_eq!(position.zobrist_hash(), transposed.zobrist_hash());
+        assert_eq!(position.zobrist_hash(), position.compute_zobrist_hash());
+
+        for m in played.into_iter().rev(){
+            position.unmake_move(m);
+        }
+        assert_eq!(position.zobrist_hash(), start_hash);
+
+        assert_ne!(start_hash, position.with_side_to_move(Color::Black).zobrist_hash());
+
+        // the same pieces played by other rules are another position
+        position.set_rules(Rules::ULTIMA);
+        assert_ne!(position.zobrist_hash(), start_hash);
+        assert_eq!(position.zobrist_hash(), position.compute_zobrist_hash());
+        position.set_rules(Rules::HYPERCHESS);
+        assert_eq!(position.zobrist_hash(), start_hash);
+    }
+
     #[test]
     fn bad_fen_test(){
 
         let position: Result<_> = Position::from_FEN("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
 
         assert!(position.is_err());
+
+        // found by fuzz/fuzz_targets/from_fen.rs, these used to panic or
+        // put pieces on the wrong squares
+        let fens = [
+            "8/8/8/8/8/8/8/8/p w 0 1",
+            "ppppppppp/8/8/8/8/8/8/8 w 0 1",
+            "8/8/8/8/8/8/8/8/8/8 w 0 1",
+            "8/8/8/8/8/8/8/7pp w 0 1",
+            "9/8/8/8/8/8/8/8 w 0 1",
+            "88888888/8 w 0 1",
+            "0p7/8/8/8/8/8/8/8 w 0 1",
+            "k w 0 1",
+            " w 0 1",
+        ];
+
+        for fen in fens{
+            assert!(Position::from_FEN(fen).is_err(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn unmove_test(){
+
+        // stradler, coordinator, springer, retractor, chameleon and king captures
+        let fens = [
+            "unbqkbn1/pppppppp/3PPP2/3r1B2/8/8/PPP3PP/RNBQK1NU w 0 8",
+            "un1q1kb1/pp1ppppn/P2PPP1p/4RB1b/8/2p4P/1PPK2P1/1NBQ2NU b 0 16",
+            "p1PP1P1k/4pp1N/Pp2P2p/3Pp3/p1Pq1P2/1Bp2b1B/P2p4/3Pp1pK w 0 -",
+            "4p2k/1b2p3/6p1/5p2/1p1pN2p/8/2n1P3/K6n w 0 -",
+            "Pk5p/r6B/8/8/p4B2/8/8/K4n1p w 0 -",
+            "pp5k/pp6/8/p1qp1QP1/PB1pBp2/3p4/8/7K w 0 -",
+            "k3r2K/8/8/4P3/4p3/2Pp1pP1/4B3/4q3 w 0 -",
+            "k4p2/8/3pKB2/8/8/5p2/8/8 w 0 -",
+        ];
+
+        for fen in fens{
+
+            let position = Position::from_FEN(fen).unwrap();
+
+            for m in position.generate_moves(){
+
+                let mut after = position.clone();
+                after.make_move(m);
+
+                let unmoves = after.generate_unmoves();
+                assert!(unmoves.contains(&m), "{fen} {m:?}");
+
+                for u in unmoves{
+                    let mut before = after.clone();
+                    before.unmake_move(u);
+                    assert!(before.generate_moves().iter().any(|g| *g == u), "{fen} {m:?} {u:?}");
+
+                    before.make_move(u);
+                    assert_eq!(before.to_FEN(), after.to_FEN());
+                    assert_eq!(before.zobrist_hash(), after.zobrist_hash());
+                }
+            }
+        }
+
+        // only the black stradlers could have just moved, from any of the empty squares below them
+        let unmoves = Position::from_start_position().generate_unmoves();
+        assert_eq!(unmoves.len(), 8*4);
+        assert!(unmoves.iter().all(|m| m.get_piece() == Piece::Stradler && !m.is_capture()));
+    }
+
+    #[test]
+    fn try_make_move_test(){
+
+        // white is in check, so some of the psuedolegal moves are illegal
+        let fen = "4k3/8/2r1b3/3P4/1n1B1q2/8/3U4/4K3 w 0 1";
+        let mut position = Position::from_FEN(fen).unwrap();
+
+        let mut illegal = 0;
+        for m in position.generate_moves(){
+            let legal = position.is_move_legal(m);
+
+            match position.try_make_move(m){
+                Ok(undo) => {
+                    assert!(legal);
+                    assert_eq!(undo.get_move(), m);
+                    position.undo_move(undo).unwrap();
+                },
+                Err(_) => {
+                    assert!(!legal);
+                    illegal += 1;
+                }
+            }
+            assert_eq!(position.to_FEN(), fen);
+        }
+        assert!(illegal > 0);
+
+        // moves from another position, including ones for a piece that isn't there
+        let mut start = Position::from_start_position();
+        for m in start.generate_moves(){
+            if !position.generate_moves().iter().any(|g| *g == m){
+                assert!(position.try_make_move(m).is_err());
+            }
+        }
+        assert_eq!(position.to_FEN(), fen);
+
+        // right squares and piece, wrong capture bits
+        let m = start.parse_move("a2a4").unwrap();
+        let mut stale = m;
+        stale.set_c1_piece(Piece::Stradler);
+        assert!(start.try_make_move(stale).is_err());
+
+        let undo = start.try_make_move(m).unwrap();
+        start.undo_move(undo).unwrap();
+        assert_eq!(start.to_FEN(), Position::from_start_position().to_FEN());
+
+        // an undo for another position is refused
+        assert!(start.undo_move(undo).is_err());
+        assert_eq!(start.to_FEN(), Position::from_start_position().to_FEN());
+
+        // the same position again after the kings go back and forth
+        let mut kings = Position::from_FEN("k7/8/8/8/8/8/8/K7 w 0 1").unwrap();
+        let first = kings.try_make_move(kings.parse_move("a1b1").unwrap()).unwrap();
+        let mut last = first;
+        for notation in ["a8b8", "b1a1", "b8a8", "a1b1"]{
+            last = kings.try_make_move(kings.parse_move(notation).unwrap()).unwrap();
+        }
+        assert_eq!(kings.zobrist_hash(), Position::from_FEN("k7/8/8/8/8/8/8/1K6 b 0 1").unwrap().zobrist_hash());
+        assert!(kings.undo_move(first).is_err());
+        assert!(kings.undo_move(last).is_ok());
+        assert_eq!(kings.to_FEN(), "k7/8/8/8/8/8/8/K7 w 4 1");
+    }
+
+    #[test]
+    fn validate_test(){
+
+        let fens = [
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+            "unbqkbn1/pppppppp/3PPP2/3r1B2/8/8/PPP3PP/RNBQK1NU w 0 8",
+            "4k3/8/2r1b3/3P4/1n1B1q2/8/3U4/4K3 w 0 1",
+        ];
+        for fen in fens{
+            assert_eq!(Position::from_FEN(fen).unwrap().validate(), Ok(()), "{fen}");
+        }
+
+        let issues = |fen| Position::from_FEN(fen).unwrap().validate().unwrap_err();
+
+        assert_eq!(issues("8/8/8/8/8/8/8/8 w 0 1"), [PositionIssue::KingCount('K', 0), PositionIssue::KingCount('k', 0)]);
+        assert_eq!(issues("k6k/8/8/8/8/8/8/K7 b 0 1"), [PositionIssue::KingCount('k', 2)]);
+        assert_eq!(issues("k7/8/8/8/8/8/8/KRRQQUUB w 0 1"), [
+            PositionIssue::TooMany('R', 2),
+            PositionIssue::TooMany('Q', 2),
+            PositionIssue::TooMany('U', 2),
+        ]);
+        assert_eq!(issues("k7/8/8/8/8/8/8/KBBB4 w 0 1"), [PositionIssue::TooMany('B', 3)]);
+
+        // white just left its king in check
+        assert_eq!(issues("4k3/8/2r1b3/3P4/1n1B1q2/8/3U4/4K3 b 0 1"), [PositionIssue::KingCapturable('K')]);
+    }
+
+    #[test]
+    fn start_position_rules_test(){
+
+        assert_eq!(Position::from_start_position().rules(), Rules::HYPERCHESS);
+
+        let position = Position::from_start_position_with_rules(Rules::ULTIMA);
+        assert_eq!(position.rules(), Rules::ULTIMA);
+        assert_eq!(position.to_FEN(), "rnbqkbnu/pppppppp/8/8/8/8/PPPPPPPP/UNBQKBNR w 0 1");
+
+        let position = Position::from_start_position_with_rules(Rules::BAROQUE);
+        assert_eq!(position.to_FEN(), Position::from_start_position().to_FEN());
+    }
+
+    #[test]
+    fn springer_multi_capture_test(){
+
+        // stradlers on c3, e5 and g7 with an empty square behind each
+        for (fen, piece) in [
+            ("k7/6p1/8/4p3/8/2p5/8/N6K w 0 1", Piece::Springer),
+            ("k7/6n1/8/4n3/8/2n5/8/B6K w 0 1", Piece::Chameleon),
+        ]{
+            let mut position = Position::from_FEN(fen).unwrap();
+
+            let leaps = |position: &Position| -> Vec<String>{
+                let mut result: Vec<String> = position.generate_moves().iter()
+                    .filter(|m| m.get_piece() == piece && m.is_capture())
+                    .map(|m| m.to_string())
+                    .collect();
+                result.sort();
+                result
+            };
+
+            assert_eq!(leaps(&position), ["A1D4"]);
+
+            position.set_rules(Rules::ULTIMA);
+            assert_eq!(leaps(&position), ["A1D4", "A1F6", "A1H8"]);
+
+            for m in position.generate_moves().into_iter().filter(|m| m.get_piece() == piece && m.is_capture()){
+                let taken = m.get_from().distance(m.get_to()) / 2;
+                let mut after = position.clone();
+                after.make_move(m);
+
+                assert!(after.is_consistent());
+                assert_eq!(after.bitboards[Color::Black].count(), 4 - taken as u32, "{m}");
+                assert!(after.generate_unmoves().contains(&m), "{m}");
+
+                after.unmake_move(m);
+                assert_eq!(after.to_FEN(), fen);
+                assert_eq!(after.zobrist_hash(), position.zobrist_hash());
+            }
+        }
+
+        // a leap stops at a piece without an empty square behind it or a friendly piece
+        let mut position = Position::from_FEN("k7/6p1/5p2/4p3/8/2p5/8/N6K w 0 1").unwrap();
+        position.set_rules(Rules::ULTIMA);
+        assert!(position.parse_move("a1f6").is_none());
+        assert!(position.parse_move("a1d4").is_some());
+
+        let mut position = Position::from_FEN("k7/6p1/8/4P3/8/2p5/8/N6K w 0 1").unwrap();
+        position.set_rules(Rules::ULTIMA);
+        assert!(position.parse_move("a1f6").is_none());
+        assert!(position.parse_move("a1h8").is_none());
+    }
+
+    #[test]
+    fn chameleon_interactions_test(){
+
+        // (fen, move, placement after the move), the chameleon takes each
+        // piece the way that piece captures and nothing else that way
+        for (fen, m, after) in [
+            // stradler, pinned against a friendly stradler or chameleon
+            ("k7/8/8/8/1Pp4B/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1P1B4/8/8/K7")),
+            ("k7/8/8/8/1Bp4B/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1B1B4/8/8/K7")),
+            ("k7/8/8/8/1Pn4B/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1PnB4/8/8/K7")),
+            ("k7/8/8/8/1Pb4B/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1PbB4/8/8/K7")),
+            // coordinator, on a death square of the chameleon and the king
+            ("k7/8/8/8/8/8/8/K2r3B w 0 1", "h1d5", Some("k7/8/8/3B4/8/8/8/K7")),
+            ("k7/8/8/8/8/8/8/K2b3B w 0 1", "h1d5", Some("k7/8/8/3B4/8/8/8/K2b4")),
+            // king, by displacement or coordinated with the coordinator
+            ("8/8/8/8/8/2k5/1B6/K7 w 0 1", "b2c3", Some("8/8/8/8/8/2B5/8/K7")),
+            ("k7/8/8/8/8/2b5/1B6/K7 w 0 1", "b2c3", None),
+            ("8/8/8/8/7k/2B5/8/K6R w 0 1", "c3d4", Some("8/8/8/8/3B4/8/8/K6R")),
+            ("k7/8/8/8/7b/2B5/8/K6R w 0 1", "c3d4", Some("k7/8/8/8/3B3b/8/8/K6R")),
+            ("8/8/8/7k/8/2B5/8/K6R w 0 1", "c3e5", Some("8/8/8/4B2k/8/8/8/K6R")),
+            // retractor, by stepping away from it
+            ("k7/8/8/8/3q4/3B4/8/K7 w 0 1", "d3d2", Some("k7/8/8/8/8/8/3B4/K7")),
+            ("k7/8/8/8/3b4/3B4/8/K7 w 0 1", "d3d2", Some("k7/8/8/8/3b4/8/3B4/K7")),
+            // springer, by leaping over it
+            ("k7/8/8/8/3n4/8/3B4/K7 w 0 1", "d2d5", Some("k7/8/8/3B4/8/8/8/K7")),
+            ("k7/8/8/8/3b4/8/3B4/K7 w 0 1", "d2d5", None),
+            // partnering a stradler, the coordinator or the king
+            ("k7/8/8/8/1Bp4P/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1B1P4/8/8/K7")),
+            ("k7/8/8/8/1Bn4P/8/8/K7 w 0 1", "h4d4", Some("k7/8/8/8/1BnP4/8/8/K7")),
+            ("K7/8/8/8/8/8/R6k/7B w 0 1", "a2d2", Some("K7/8/8/8/8/8/3R4/7B")),
+            ("K3k3/8/8/8/8/8/R6q/7B w 0 1", "a2d2", Some("K3k3/8/8/8/8/8/3R3q/7B")),
+            ("k6B/8/8/8/8/8/7r/K7 w 0 1", "a1b2", Some("k6B/8/8/8/8/8/1K6/8")),
+            ("k6B/8/8/8/8/8/7p/K7 w 0 1", "a1b2", Some("k6B/8/8/8/8/8/1K5p/8")),
+        ]{
+            let mut position = Position::from_FEN(fen).unwrap();
+            let parsed = position.parse_move(m);
+
+            let Some(after) = after else{
+                assert!(parsed.is_none(), "{fen} {m}");
+                continue;
+            };
+
+            let parsed = parsed.unwrap();
+            position.make_move(parsed);
+            assert_eq!(position.to_FEN().split(' ').next().unwrap(), after, "{fen} {m}");
+            assert!(position.generate_unmoves().contains(&parsed), "{fen} {m}");
+
+            position.unmake_move(parsed);
+            assert_eq!(position.to_FEN(), fen);
+        }
+
+        // a chameleon next to the enemy immobilizer is immobilized, and
+        // immobilizes it only if the rules say so
+        let mut position = Position::from_FEN("k7/8/8/8/3u4/3B4/8/K7 b 0 1").unwrap();
+        let immobilizer_moves = |position: &Position| position.generate_moves().iter().filter(|m| m.get_piece() == Piece::Immobilizer).count();
+
+        assert_eq!(immobilizer_moves(&position), 0);
+        position.set_rules(Rules::ULTIMA);
+        assert_eq!(immobilizer_moves(&position), 23);
+
+        let position = Position::from_FEN("k7/8/8/8/3u4/3B4/8/K7 w 0 1").unwrap();
+        assert!(position.generate_moves().iter().all(|m| m.get_piece() != Piece::Chameleon));
+
+        // but chameleons don't immobilize each other
+        let position = Position::from_FEN("k7/8/8/8/3b4/3B4/8/K7 w 0 1").unwrap();
+        assert!(position.generate_moves().iter().any(|m| m.get_piece() == Piece::Chameleon));
+    }
+    #[test]
+    fn bytes_round_trip_test(){
+
+        let mut positions: Vec<Position> = [
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+            "4k3/1p3p2/2bU4/8/8/6B1/3u4/4K3 b 37 4000000000",
+            "k7/8/8/8/8/8/8/K7 w 0 0",
+        ].iter().map(|fen| Position::from_FEN(fen).unwrap()).collect();
+
+        positions.push(Position::from_start_position_with_rules(Rules::ULTIMA));
+
+        // a full board needs every nibble
+        let mut full = Position::from_FEN("k7/8/8/8/8/8/8/K7 w 0 1").unwrap();
+        for s in Square::ALL{
+            if full.board[s] != Piece::Empty{
+                continue;
+            }
+            full.place_piece(if s.rank() < 4 {Color::White} else {Color::Black}, Piece::Stradler, s);
+        }
+        positions.push(full);
+
+        for position in positions{
+            let bytes = position.to_bytes();
+            let read = Position::from_bytes(&bytes).unwrap();
+
+            assert_eq!(read.to_FEN(), position.to_FEN());
+            assert_eq!(read.rules(), position.rules());
+            assert_eq!(read.zobrist_hash(), position.zobrist_hash());
+            assert!(read.is_consistent());
+            assert_eq!(read.to_bytes(), bytes);
+        }
+
+        // sorting the bytes sorts by placement first
+        let a = Position::from_FEN("k7/8/8/8/8/8/8/K7 b 9 9").unwrap().to_bytes();
+        let b = Position::from_FEN("k7/8/8/8/8/8/8/1K6 w 0 1").unwrap().to_bytes();
+        assert!(a < b);
+
+        let good = Position::from_start_position().to_bytes();
+        assert!(Position::from_bytes(&good[..POSITION_BYTES-1]).is_err());
+
+        for (index, value) in [
+            (0, 2),     // version
+            (9, 0x81),  // black empty piece
+            (9, 0x01),  // white empty piece
+            (40, 0x01), // nibble past the last piece
+            (41, 0x10), // unknown flag
+        ]{
+            let mut bad = good;
+            bad[index] = value;
+            assert!(Position::from_bytes(&bad).is_err(), "{index}");
+        }
+
+        assert_eq!(good[0], BYTES_VERSION);
+        assert!(Position::from_bytes_validated(&good).is_ok());
+
+        // readable, but not a position the library can play
+        let kings = Position::from_FEN("kkkkkkkk/kkkkkkkk/kkkkkkkk/kkkkkkkk/KKKKKKKK/KKKKKKKK/KKKKKKKK/KKKKKKKK w 0 1").unwrap().to_bytes();
+        assert!(Position::from_bytes(&kings).is_ok());
+        assert!(Position::from_bytes_validated(&kings).is_err());
+        assert!(Position::from_bytes_validated(&good[..POSITION_BYTES-1]).is_err());
+    }
+    #[test]
+    fn flip_test(){
+
+        let start = Position::from_start_position();
+
+        let flipped = start.flipped();
+        assert_eq!(flipped.to_FEN(), "rnbqkbnu/pppppppp/8/8/8/8/PPPPPPPP/UNBQKBNR b 0 1");
+        assert!(flipped.is_consistent());
+
+        let mirrored = start.mirrored_horizontal();
+        assert_eq!(mirrored.to_FEN(), "rnbkqbnu/pppppppp/8/8/8/8/PPPPPPPP/UNBKQBNR w 0 1");
+        assert!(mirrored.is_consistent());
+
+        let position = Position::from_FEN("4k3/1p3p2/2bU4/8/8/6B1/3u4/4K3 b 37 12").unwrap();
+        assert_eq!(position.flipped().to_FEN(), "4k3/3U4/6b1/8/8/2Bu4/1P3P2/4K3 w 37 12");
+        assert_eq!(position.mirrored_horizontal().to_FEN(), "3k4/2p3p1/4Ub2/8/8/1B6/4u3/3K4 b 37 12");
+
+        for transformed in [position.flipped().flipped(), position.mirrored_horizontal().mirrored_horizontal()]{
+            assert_eq!(transformed.to_FEN(), position.to_FEN());
+            assert_eq!(transformed.zobrist_hash(), position.zobrist_hash());
+        }
+    }
+    #[test]
+    fn move_sink_test(){
+
+        use crate::r#move::MoveCount;
+
+        // keeps the first limit moves
+        struct Limit(Vec<Move>, usize);
+        impl MoveSink for Limit{
+            fn push(&mut self, m: Move) -> ControlFlow<()>{
+                self.0.push(m);
+                if self.0.len() == self.1 {ControlFlow::Break(())} else {ControlFlow::Continue(())}
+            }
+        }
+
+        for fen in [
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+            "k7/6p1/8/4p3/8/2p5/8/N6K w 0 1",
+            "4k3/1p3p2/2bU4/8/8/6B1/3u4/4K3 b 0 1",
+        ]{
+            let mut position = Position::from_FEN(fen).unwrap();
+            position.set_rules(Rules::ULTIMA);
+
+            let moves: Vec<Move> = position.generate_moves().into_iter().collect();
+
+            let mut count = MoveCount::default();
+            position.generate_moves_into(&mut count);
+            assert_eq!(count.0, moves.len());
+
+            let legal: Vec<Move> = moves.iter().copied().filter(|m| position.is_move_legal(*m)).collect();
+            assert_eq!(position.generate_legal_moves().into_iter().collect::<Vec<_>>(), legal);
+
+            let mut first = FirstMove::default();
+            position.generate_moves_into(&mut first);
+            assert_eq!(first.0, moves.first().copied());
+
+            // breaking after any move, including from inside a springer leap
+            for limit in 1..=moves.len(){
+                let mut sink = Limit(Vec::new(), limit);
+                position.generate_moves_into(&mut sink);
+                assert_eq!(sink.0, moves[..limit]);
+            }
+
+            // through a trait object as well
+            let mut count = MoveCount::default();
+            let sink: &mut dyn MoveSink = &mut count;
+            position.generate_legal_moves_into(sink);
+            assert_eq!(count.0, legal.len());
+            assert_eq!(position.to_FEN(), Position::from_FEN(fen).unwrap().to_FEN());
+        }
+
+        // the immobilized king has no moves
+        let mut position = Position::from_FEN("k7/1U6/2K5/8/8/8/8/8 b 0 1").unwrap();
+        assert!(!position.has_legal_move());
+        assert!(Position::from_start_position().has_legal_move());
     }
 
+    #[test]
+    fn generate_moves_filtered_test(){
+
+        let targets = Bitboard(0x00FF_0000_FF00_00FF);
+
+        let mut filters = vec![GenFilter::ALL, GenFilter::CAPTURES, GenFilter::QUIET, GenFilter{ captures: false, ..GenFilter::QUIET }];
+        for filter in [GenFilter::ALL, GenFilter::CAPTURES, GenFilter::QUIET]{
+            filters.push(GenFilter{ targets, ..filter });
+            for piece in Piece::ALL{
+                filters.push(GenFilter{ piece: Some(piece), ..filter });
+            }
+        }
+
+        for fen in [
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1",
+            "k7/6p1/8/4p3/8/2p5/8/N6K w 0 1",
+            "k7/6n1/8/4n3/8/2n5/8/B6K w 0 1",
+            "4k3/1p3p2/2bU4/8/8/6B1/3u4/4K3 b 0 1",
+            "k7/8/8/8/1Pp4B/8/8/K7 w 0 1",
+            "k7/8/8/8/7b/2B5/8/K6R w 0 1",
+            "K3k3/8/8/8/8/8/R6q/7B w 0 1",
+            "k7/8/8/8/3n4/8/3B4/K7 w 0 1",
+        ]{
+            for rules in [Rules::HYPERCHESS, Rules::ULTIMA]{
+                let mut position = Position::from_FEN(fen).unwrap();
+                position.set_rules(rules);
+
+                let moves = position.generate_moves();
+
+                for filter in &filters{
+                    let expected: Vec<Move> = moves.iter().copied().filter(|m| filter.allows(*m)).collect();
+                    assert_eq!(position.generate_moves_filtered(*filter).into_iter().collect::<Vec<_>>(), expected, "{fen} {filter:?}");
+                }
+            }
+        }
+
+        let position = Position::from_start_position();
+        assert_eq!(position.generate_moves_filtered(GenFilter::CAPTURES).len(), 0);
+        assert_eq!(position.generate_moves_filtered(GenFilter{ piece: Some(Piece::Immobilizer), ..GenFilter::ALL }).len(), 0);
+        assert_eq!(position.generate_moves_filtered(GenFilter::default()).len(), position.generate_moves().len());
+    }
+
+    #[test]
+    fn threats_test(){
+
+        // the chameleon leaps the springer and the springer leaps the chameleon
+        let position = Position::from_FEN("k7/8/8/8/3n4/8/3B4/K7 w 0 1").unwrap();
+        let threats = position.threats(Color::White);
+        assert_eq!(threats.attacked(), Bitboard::from(Square::D4));
+        assert_eq!(threats.captures_on(Square::D4).map(|m| m.to_string()).collect::<Vec<_>>(), ["D2D5"]);
+        assert_eq!(threats.attackers[Square::D4], Bitboard::from(Square::D2));
+        assert_eq!(position.threats(Color::Black).attacked(), Bitboard::from(Square::D2));
+        assert_eq!(Position::from_start_position().threats(Color::Black).attacked(), Bitboard::EMPTY);
+
+        let position = Position::from_FEN("k7/1U6/2K5/8/8/8/8/8 b 0 1").unwrap();
+        assert_eq!(position.threats(Color::White).immobilized, Bitboard::from(Square::A8));
+        assert_eq!(position.threats(Color::Black).immobilized, Bitboard::EMPTY);
+
+        let mut position = Position::from_FEN("k7/8/8/8/8/2u5/1B6/K7 w 0 1").unwrap();
+        assert_eq!(position.threats(Color::White).immobilized, Bitboard::from(Square::C3));
+        assert_eq!(position.threats(Color::Black).immobilized, Bitboard::from(Square::B2));
+        position.set_rules(Rules::ULTIMA);
+        assert_eq!(position.threats(Color::White).immobilized, Bitboard::EMPTY);
+
+        // the coordinator going to h8 takes the king on a8
+        let position = Position::from_FEN("k7/8/8/8/8/8/8/K6R w 0 1").unwrap();
+        let threats = position.threats(Color::White);
+        assert!(threats.death_squares.contains(Square::A8));
+        assert!(!threats.death_squares.contains(Square::A1) && !threats.death_squares.contains(Square::H1));
+        assert!(threats.captures_on(Square::A8).any(|m| m.to_string() == "H1H8"));
+
+        // not with the coordinator immobilized
+        let position = Position::from_FEN("k7/8/8/8/8/8/6u1/K6R w 0 1").unwrap();
+        assert!(!position.threats(Color::White).death_squares.contains(Square::A8));
+        assert!(position.threats(Color::White).captures_on(Square::A8).next().is_none());
+
+        // the decoded squares are the ones make_move takes pieces from
+        for rules in [Rules::default(), Rules::ULTIMA]{
+            let mut position = Position::from_start_position_with_rules(rules);
+            for ply in 0..200{
+                for m in position.generate_moves().iter(){
+                    let before = position.bitboards[!position.to_play];
+                    position.make_move(*m);
+                    let taken = before & !position.bitboards[position.to_play];
+                    position.unmake_move(*m);
+                    assert_eq!(position.captured_squares(position.to_play, *m), taken, "{m:?}");
+                }
+                let legal = position.generate_legal_moves();
+                if legal.is_empty(){
+                    break;
+                }
+                position.make_move(legal.get(ply * 7 % legal.len()).unwrap());
+            }
+        }
+    }
 }
@@ -0,0 +1,181 @@
+use crate::position::Position;
+use crate::r#move::Move;
+
+use std::fmt;
+use std::error;
+
+#[derive(Debug)]
+pub struct ReadRecordError{}
+impl fmt::Display for ReadRecordError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading game record")
+    }
+}
+impl error::Error for ReadRecordError{}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult{
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+impl fmt::Display for GameResult{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let to_write = match self{
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+        };
+        write!(f, "{}", to_write)
+    }
+}
+
+/// A finished game, written as a list of tag lines followed by the moves
+///
+/// ```text
+/// [White "depth3"]
+/// [Black "depth2"]
+/// [FEN "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1"]
+/// [Result "1-0"]
+/// E2E4 E7E5 ...
+/// ```
+///
+/// games in a file are separated by a blank line
+#[derive(Debug, Clone)]
+pub struct GameRecord{
+    pub white: String,
+    pub black: String,
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+}
+impl GameRecord{
+
+    /// Reads every game in a record file
+    ///
+    /// moves are replayed from the starting FEN, so a record containing
+    /// a move that is not psuedolegal is rejected
+    pub fn read_all(records: &str) -> Result<Vec<GameRecord>, ReadRecordError>{
+
+        let mut result: Vec<GameRecord> = Vec::new();
+        let mut lines = records.lines().map(str::trim).peekable();
+
+        loop{
+            while lines.next_if(|l| l.is_empty()).is_some(){}
+
+            if lines.peek().is_none(){
+                break;
+            }
+
+            let mut white = None;
+            let mut black = None;
+            let mut start_fen = None;
+            let mut game_result = None;
+
+            while let Some(tag) = lines.next_if(|l| l.starts_with('[')){
+                let (name, value) = Self::read_tag(tag)?;
+                match name{
+                    "White" => white = Some(value.to_string()),
+                    "Black" => black = Some(value.to_string()),
+                    "FEN" => start_fen = Some(value.to_string()),
+                    "Result" => game_result = Some(match value{
+                        "1-0" => GameResult::WhiteWins,
+                        "0-1" => GameResult::BlackWins,
+                        "1/2-1/2" => GameResult::Draw,
+                        _ => return Err(ReadRecordError{}),
+                    }),
+                    // unknown tags are skipped
+                    _ => {},
+                }
+            }
+
+            let start_fen = start_fen.ok_or(ReadRecordError{})?;
+            let mut position = Position::from_FEN(&start_fen).map_err(|_| ReadRecordError{})?;
+            let mut moves: Vec<Move> = Vec::new();
+
+            while let Some(line) = lines.next_if(|l| !l.is_empty()){
+                for notation in line.split_whitespace(){
+                    let m = position.parse_move(notation).ok_or(ReadRecordError{})?;
+                    position.make_move(m);
+                    moves.push(m);
+                }
+            }
+
+            result.push(GameRecord{
+                white: white.unwrap_or_default(),
+                black: black.unwrap_or_default(),
+                start_fen,
+                moves,
+                result: game_result.ok_or(ReadRecordError{})?,
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn read_tag(tag: &str) -> Result<(&str, &str), ReadRecordError>{
+        let inner = tag.strip_prefix('[').and_then(|t| t.strip_suffix(']')).ok_or(ReadRecordError{})?;
+        let (name, value) = inner.split_once(' ').ok_or(ReadRecordError{})?;
+        let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or(ReadRecordError{})?;
+
+        Ok((name, value))
+    }
+}
+impl fmt::Display for GameRecord{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        writeln!(f, "[White \"{}\"]", self.white)?;
+        writeln!(f, "[Black \"{}\"]", self.black)?;
+        writeln!(f, "[FEN \"{}\"]", self.start_fen)?;
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+
+        let moves: Vec<String> = self.moves.iter().map(Move::to_string).collect();
+        writeln!(f, "{}", moves.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    #[test]
+    fn record_round_trip_test(){
+
+        let mut position = Position::from_start_position();
+        let mut moves = Vec::new();
+
+        for notation in ["e2e4", "e7e5", "d2d3"]{
+            let m = position.parse_move(notation).unwrap();
+            position.make_move(m);
+            moves.push(m);
+        }
+
+        let record = GameRecord{
+            white: String::from("a"),
+            black: String::from("b"),
+            start_fen: Position::from_start_position().to_FEN(),
+            moves,
+            result: GameResult::Draw,
+        };
+
+        let written = format!("{record}\n{record}");
+        let read = GameRecord::read_all(&written).unwrap();
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].white, "a");
+        assert_eq!(read[0].black, "b");
+        assert_eq!(read[0].moves, record.moves);
+        assert_eq!(read[1].result, GameResult::Draw);
+    }
+
+    #[test]
+    fn bad_record_test(){
+
+        // e5e7 would have to jump over the stradler on e6
+        let record = "[FEN \"unbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNU w 0 1\"]\n[Result \"1-0\"]\ne4e5 e7e6 e5e7\n";
+
+        assert!(GameRecord::read_all(record).is_err());
+        assert!(GameRecord::read_all("[Result \"1-0\"]\ne2e4\n").is_err());
+    }
+}
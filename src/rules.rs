@@ -0,0 +1,176 @@
+use std::fmt;
+use std::error;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct ParseRulesError{}
+impl fmt::Display for ParseRulesError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading rules")
+    }
+}
+impl error::Error for ParseRulesError{}
+
+/// The rule variants a Position is played by
+///
+/// can be read from the name of a rule set followed by a comma separated
+/// list of key=value pairs changing single rules, e.g. "ultima" or
+/// "hyperchess,chameleon_immobilizes_immobilizer=false"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules{
+    /// a chameleon next to the enemy immobilizer immobilizes it. the
+    /// immobilizer freezes the chameleon in turn and never captures, so the
+    /// two stay stuck until another piece takes one of them
+    pub chameleon_immobilizes_immobilizer: bool,
+    /// a springer can take several pieces on one line in a single leap, as
+    /// long as each has an empty square behind it. it still lands right
+    /// behind the last piece taken
+    pub springer_multi_capture: bool,
+    /// the immobilizer starts on the player's left and the coordinator on
+    /// the right, instead of the other way around
+    pub immobilizer_on_left: bool,
+}
+impl Rules{
+
+    /// the rules this crate was written for
+    pub const HYPERCHESS: Rules = Rules{
+        chameleon_immobilizes_immobilizer: true,
+        springer_multi_capture: false,
+        immobilizer_on_left: false,
+    };
+
+    /// Ultima with the springer a long leaper, where only the enemy
+    /// immobilizer immobilizes the immobilizer. Abbott's published rules
+    /// also let a chameleon do it, set chameleon_immobilizes_immobilizer
+    /// for those
+    pub const ULTIMA: Rules = Rules{
+        chameleon_immobilizes_immobilizer: false,
+        springer_multi_capture: true,
+        immobilizer_on_left: true,
+    };
+
+    /// Ultima with the coordinator and immobilizer swapped in the starting position
+    pub const BAROQUE: Rules = Rules{
+        immobilizer_on_left: false,
+        ..Rules::ULTIMA
+    };
+
+    /// FEN of the starting position under these rules
+    pub fn starting_fen(&self) -> &'static str{
+        if self.immobilizer_on_left{
+            "rnbqkbnu/pppppppp/8/8/8/8/PPPPPPPP/UNBQKBNR w 0 1"
+        }
+        else{
+            "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1"
+        }
+    }
+}
+impl Default for Rules{
+    fn default() -> Self{
+        Self::HYPERCHESS
+    }
+}
+/// the name of the nearest rule set followed by the rules that differ
+/// from it, in the form read by from_str
+impl fmt::Display for Rules{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let options = |rules: &Rules| [
+            ("chameleon_immobilizes_immobilizer", rules.chameleon_immobilizes_immobilizer),
+            ("springer_multi_capture", rules.springer_multi_capture),
+            ("immobilizer_on_left", rules.immobilizer_on_left),
+        ];
+
+        let (name, base) = [("hyperchess", Rules::HYPERCHESS), ("ultima", Rules::ULTIMA), ("baroque", Rules::BAROQUE)]
+            .into_iter()
+            .min_by_key(|(_, base)| options(base).into_iter().zip(options(self)).filter(|(a, b)| a != b).count())
+            .unwrap();
+
+        write!(f, "{name}")?;
+
+        for ((key, value), (_, base_value)) in options(self).into_iter().zip(options(&base)){
+            if value != base_value{
+                write!(f, ",{key}={value}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+impl FromStr for Rules{
+    type Err = ParseRulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+
+        let mut options = s.split(',').map(str::trim);
+
+        let mut result = match options.next().unwrap_or("").to_ascii_lowercase().as_str(){
+            "hyperchess" => Rules::HYPERCHESS,
+            "ultima" => Rules::ULTIMA,
+            "baroque" => Rules::BAROQUE,
+            _ => return Err(ParseRulesError{}),
+        };
+
+        for option in options.filter(|o| !o.is_empty()){
+
+            let (key, value) = option.split_once('=').ok_or(ParseRulesError{})?;
+            let value: bool = value.parse().map_err(|_| ParseRulesError{})?;
+
+            match key{
+                "chameleon_immobilizes_immobilizer" => result.chameleon_immobilizes_immobilizer = value,
+                "springer_multi_capture" => result.springer_multi_capture = value,
+                "immobilizer_on_left" => result.immobilizer_on_left = value,
+                _ => return Err(ParseRulesError{}),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    #[test]
+    fn parse_rules_test(){
+
+        assert_eq!("hyperchess".parse::<Rules>().unwrap(), Rules::default());
+        assert_eq!("Ultima".parse::<Rules>().unwrap(), Rules::ULTIMA);
+        assert_eq!(" baroque ".parse::<Rules>().unwrap(), Rules::BAROQUE);
+
+        let rules: Rules = "ultima, chameleon_immobilizes_immobilizer=true".parse().unwrap();
+        assert!(rules.chameleon_immobilizes_immobilizer);
+        assert!(rules.immobilizer_on_left);
+
+        let rules: Rules = "hyperchess,springer_multi_capture=true".parse().unwrap();
+        assert!(rules.springer_multi_capture);
+        assert_eq!(Rules{ springer_multi_capture: false, ..rules }, Rules::HYPERCHESS);
+
+        assert!("".parse::<Rules>().is_err());
+        assert!("chess".parse::<Rules>().is_err());
+        assert!("ultima,immobilizer_on_left".parse::<Rules>().is_err());
+        assert!("ultima,immobilizer_on_left=1".parse::<Rules>().is_err());
+        assert!("ultima,springer=true".parse::<Rules>().is_err());
+    }
+
+    #[test]
+    fn display_rules_test(){
+
+        assert_eq!(Rules::HYPERCHESS.to_string(), "hyperchess");
+        assert_eq!(Rules::BAROQUE.to_string(), "baroque");
+
+        let rules = Rules{ chameleon_immobilizes_immobilizer: true, ..Rules::ULTIMA };
+        assert_eq!(rules.to_string(), "ultima,chameleon_immobilizes_immobilizer=true");
+
+        for chameleon_immobilizes_immobilizer in [false, true]{
+            for springer_multi_capture in [false, true]{
+                for immobilizer_on_left in [false, true]{
+                    let rules = Rules{ chameleon_immobilizes_immobilizer, springer_multi_capture, immobilizer_on_left };
+                    assert_eq!(rules.to_string().parse::<Rules>().unwrap(), rules);
+                }
+            }
+        }
+    }
+}
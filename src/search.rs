@@ -0,0 +1,456 @@
+use crate::book::OpeningBook;
+use crate::tablebase::{TablebaseSet, TablebaseValue};
+use crate::position::Position;
+use crate::r#move::{Move, MoveList, MoveCount};
+use crate::types::{Color, Piece};
+
+use std::fmt;
+use std::error;
+use std::str::FromStr;
+
+/// score for capturing the enemy king, mate scores are
+/// MATE_SCORE minus the number of plies until the king is taken
+pub const MATE_SCORE: i32 = 1_000_000;
+const INFINITY: i32 = MATE_SCORE + 1;
+
+#[derive(Debug)]
+pub struct ParseConfigError{}
+impl fmt::Display for ParseConfigError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading engine config")
+    }
+}
+impl error::Error for ParseConfigError{}
+
+/// weights used by evaluate, material is in centipawns
+///
+/// mobility is added per psuedolegal move the side has over its opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalWeights{
+    pub stradler: i32,
+    pub coordinator: i32,
+    pub springer: i32,
+    pub chameleon: i32,
+    pub retractor: i32,
+    pub immobilizer: i32,
+    pub mobility: i32,
+}
+impl EvalWeights{
+    pub const DEFAULT: EvalWeights = EvalWeights{
+        stradler: 100,
+        coordinator: 500,
+        springer: 450,
+        chameleon: 400,
+        retractor: 650,
+        immobilizer: 700,
+        mobility: 2,
+    };
+
+    fn piece_value(&self, piece: Piece) -> i32{
+        match piece{
+            Piece::Stradler => self.stradler,
+            Piece::Coordinator => self.coordinator,
+            Piece::Springer => self.springer,
+            Piece::Chameleon => self.chameleon,
+            Piece::Retractor => self.retractor,
+            Piece::Immobilizer => self.immobilizer,
+            Piece::Empty | Piece::King => 0,
+        }
+    }
+}
+impl Default for EvalWeights{
+    fn default() -> Self{
+        Self::DEFAULT
+    }
+}
+
+/// Settings for one engine taking part in a match
+///
+/// can be read from a comma separated list of key=value pairs,
+/// e.g. "name=deep,depth=4,nodes=200000,mobility=0"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineConfig{
+    pub name: String,
+    pub depth: u32,
+    /// stop searching once this many nodes have been visited
+    pub nodes: Option<u64>,
+    pub weights: EvalWeights,
+}
+impl Default for EngineConfig{
+    fn default() -> Self{
+        EngineConfig{
+            name: String::from("hmg"),
+            depth: 3,
+            nodes: None,
+            weights: EvalWeights::DEFAULT,
+        }
+    }
+}
+impl FromStr for EngineConfig{
+    type Err = ParseConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+
+        let mut result = EngineConfig::default();
+
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()){
+
+            let (key, value) = option.split_once('=').ok_or(ParseConfigError{})?;
+
+            if key == "name"{
+                result.name = value.to_string();
+                continue;
+            }
+
+            let value: i64 = value.parse().map_err(|_| ParseConfigError{})?;
+            let weight = || i32::try_from(value).map_err(|_| ParseConfigError{});
+
+            match key{
+                "depth" => result.depth = u32::try_from(value).map_err(|_| ParseConfigError{})?,
+                "nodes" => result.nodes = Some(u64::try_from(value).map_err(|_| ParseConfigError{})?),
+                "stradler" => result.weights.stradler = weight()?,
+                "coordinator" => result.weights.coordinator = weight()?,
+                "springer" => result.weights.springer = weight()?,
+                "chameleon" => result.weights.chameleon = weight()?,
+                "retractor" => result.weights.retractor = weight()?,
+                "immobilizer" => result.weights.immobilizer = weight()?,
+                "mobility" => result.weights.mobility = weight()?,
+                _ => return Err(ParseConfigError{}),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchResult{
+    /// None if the side to play has no legal moves
+    pub best_move: Option<Move>,
+    /// score from the point of view of the side to play
+    pub score: i32,
+    /// deepest fully completed iteration
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Static evaluation from the point of view of the side to play
+pub fn evaluate(position: &Position, weights: &EvalWeights) -> i32{
+
+    let us = position.side_to_move();
+    let them = !us;
+
+    let mut score = 0;
+
+    for piece in &(Piece::ALL)[1..]{
+        let difference = position.pieces(us, *piece).0.count_ones() as i32 - position.pieces(them, *piece).0.count_ones() as i32;
+        score += difference * weights.piece_value(*piece);
+    }
+
+    if weights.mobility != 0{
+        let mut our_moves = MoveCount::default();
+        position.generate_moves_into(&mut our_moves);
+        let mut their_moves = MoveCount::default();
+        position.with_side_to_move(them).generate_moves_into(&mut their_moves);
+
+        score += (our_moves.0 as i32 - their_moves.0 as i32) * weights.mobility;
+    }
+
+    score
+}
+
+/// Alpha-beta searcher
+///
+/// the search works on psuedolegal moves, illegal moves are refuted by
+/// the king being captured on the next ply
+pub struct Engine{
+    config: EngineConfig,
+    book: Option<OpeningBook>,
+    tablebases: Option<TablebaseSet>,
+    nodes: u64,
+    stopped: bool,
+}
+impl Engine{
+
+    pub fn new(config: EngineConfig) -> Self{
+        Engine{
+            config,
+            book: None,
+            tablebases: None,
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn config(&self) -> &EngineConfig{
+        &self.config
+    }
+
+    /// book moves are played without searching while the book has any
+    pub fn set_book(&mut self, book: OpeningBook){
+        self.book = Some(book);
+    }
+
+    /// positions covered by the tablebases are scored from them instead of searched
+    pub fn set_tablebases(&mut self, tablebases: TablebaseSet){
+        self.tablebases = Some(tablebases);
+    }
+
+    /// Searches the position with iterative deepening up to the configured depth,
+    /// or until the node budget runs out
+    ///
+    /// position is returned to its original state once the search is done
+    pub fn search(&mut self, position: &mut Position) -> SearchResult{
+
+        self.nodes = 0;
+        self.stopped = false;
+
+        let mut result = SearchResult{
+            best_move: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+
+        if let Some(m) = self.book.as_mut().and_then(|book| book.choose(position)){
+            if position.is_move_legal(m){
+                result.best_move = Some(m);
+                return result;
+            }
+        }
+
+        let moves: MoveList = position.generate_moves();
+
+        // the opponent left their king hanging, legality checks would
+        // need a king on both sides so just take it
+        if let Some(m) = self.find_king_capture(position, &moves){
+            result.best_move = Some(m);
+            result.score = MATE_SCORE;
+            return result;
+        }
+
+        let mut root_moves: Vec<Move> = Vec::new();
+        position.generate_legal_moves_into(&mut root_moves);
+
+        result.best_move = root_moves.first().copied();
+
+        if root_moves.is_empty(){
+            result.score = -MATE_SCORE;
+            return result;
+        }
+
+        for depth in 1..=self.config.depth.max(1){
+
+            let mut alpha = -INFINITY;
+            let mut best_move = root_moves[0];
+
+            for i in 0..root_moves.len(){
+                let m = root_moves[i];
+
+                position.make_move(m);
+                let score = -self.negamax(position, depth-1, -INFINITY, -alpha, 1);
+                position.unmake_move(m);
+
+                if self.stopped{
+                    break;
+                }
+
+                if score > alpha{
+                    alpha = score;
+                    best_move = m;
+                    // search the best move first in the next iteration
+                    root_moves[..=i].rotate_right(1);
+                }
+            }
+
+            if self.stopped{
+                break;
+            }
+
+            result.best_move = Some(best_move);
+            result.score = alpha;
+            result.depth = depth;
+
+            // no need to look further once a forced king capture is found
+            if alpha.abs() >= MATE_SCORE - depth as i32{
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn find_king_capture(&self, position: &mut Position, moves: &MoveList) -> Option<Move>{
+
+        let opponent: Color = !position.side_to_move();
+
+        moves.iter().copied().filter(Move::is_capture).find(|m| {
+            position.make_move(*m);
+            let king_gone = position.pieces(opponent, Piece::King).is_empty();
+            position.unmake_move(*m);
+            king_gone
+        })
+    }
+
+    fn negamax(&mut self, position: &mut Position, depth: u32, mut alpha: i32, beta: i32, ply: i32) -> i32{
+
+        self.nodes += 1;
+
+        if let Some(limit) = self.config.nodes{
+            if self.nodes >= limit{
+                self.stopped = true;
+                return 0;
+            }
+        }
+
+        let moves: MoveList = position.generate_moves();
+
+        // taking the king ends the game, so check for it before anything else
+        if self.find_king_capture(position, &moves).is_some(){
+            return MATE_SCORE - ply;
+        }
+
+        if let Some(value) = self.tablebases.as_ref().and_then(|t| t.probe(position)){
+            return match value{
+                TablebaseValue::Win(d) => MATE_SCORE - ply - d as i32 - 1,
+                TablebaseValue::Loss(d) => -(MATE_SCORE - ply - d as i32 - 1),
+                TablebaseValue::Draw => 0,
+            };
+        }
+
+        if depth == 0{
+            return evaluate(position, &self.config.weights);
+        }
+
+        // if every move loses the king we are checkmated
+        let mut best = -(MATE_SCORE - ply - 1);
+
+        // captures first
+        let ordered = moves.iter().filter(|m| m.is_capture()).chain(moves.iter().filter(|m| !m.is_capture()));
+
+        for m in ordered{
+            position.make_move(*m);
+            let score = -self.negamax(position, depth-1, -beta, -alpha, ply+1);
+            position.unmake_move(*m);
+
+            if self.stopped{
+                return 0;
+            }
+
+            if score > best{
+                best = score;
+            }
+            if score > alpha{
+                alpha = score;
+            }
+            if alpha >= beta{
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+    use crate::tablebase::Material;
+
+    #[test]
+    fn engine_config_test(){
+
+        let config: EngineConfig = "name=quick, depth=2,nodes=5000,mobility=0".parse().unwrap();
+
+        assert_eq!(config.name, "quick");
+        assert_eq!(config.depth, 2);
+        assert_eq!(config.nodes, Some(5000));
+        assert_eq!(config.weights.mobility, 0);
+        assert_eq!(config.weights.stradler, EvalWeights::DEFAULT.stradler);
+
+        assert!("depth".parse::<EngineConfig>().is_err());
+        assert!("depth=-1".parse::<EngineConfig>().is_err());
+        assert!("queen=900".parse::<EngineConfig>().is_err());
+    }
+
+    #[test]
+    fn start_position_eval_test(){
+        let position = Position::from_start_position();
+        assert_eq!(evaluate(&position, &EvalWeights::DEFAULT), 0);
+    }
+
+    #[test]
+    fn finds_capture_test(){
+
+        // a4c4 traps the stradler on c3 against the stradler on c2
+        let fen = "7k/8/8/8/P7/2p5/2P5/7K w 0 1";
+        let mut position = Position::from_FEN(fen).unwrap();
+        let mut engine = Engine::new(EngineConfig{depth: 1, ..EngineConfig::default()});
+
+        let result = engine.search(&mut position);
+
+        assert_eq!(result.best_move, position.parse_move("a4c4"));
+        assert!(result.score >= EvalWeights::DEFAULT.stradler);
+        assert_eq!(position.to_FEN(), fen);
+    }
+
+    #[test]
+    fn book_move_test(){
+
+        let mut position = Position::from_start_position();
+        let c4 = position.parse_move("c2c4").unwrap();
+
+        let mut book = OpeningBook::new();
+        book.add(&position, c4, 1);
+
+        let mut engine = Engine::new(EngineConfig{depth: 1, ..EngineConfig::default()});
+        engine.set_book(book);
+
+        let result = engine.search(&mut position);
+        assert_eq!(result.best_move, Some(c4));
+        assert_eq!(result.nodes, 0);
+
+        // out of book
+        position.make_move(c4);
+        let result = engine.search(&mut position);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn node_limit_test(){
+
+        let mut position = Position::from_start_position();
+        let mut engine = Engine::new(EngineConfig{depth: 10, nodes: Some(2000), ..EngineConfig::default()});
+
+        let result = engine.search(&mut position);
+
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 2000);
+        assert!(result.depth < 10);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "builds the whole KUvK table, run with --release")]
+    fn tablebase_test(){
+
+        let material: Material = "KUvK".parse().unwrap();
+        let mut tablebases = TablebaseSet::new();
+        tablebases.generate(material);
+
+        // moving the immobilizer next to the king leaves it without moves, which is mate
+        let mut position = Position::from_FEN("k7/7U/2K5/8/8/8/8/8 w 0 1").unwrap();
+        assert_eq!(tablebases.probe(&position), Some(TablebaseValue::Win(1)));
+
+        let mut engine = Engine::new(EngineConfig{depth: 1, ..EngineConfig::default()});
+        engine.set_tablebases(tablebases);
+
+        let result = engine.search(&mut position);
+        assert_eq!(result.score, MATE_SCORE - 2);
+
+        position.make_move(result.best_move.unwrap());
+        assert!(position.is_checkmate());
+    }
+}
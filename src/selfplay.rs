@@ -0,0 +1,285 @@
+use crate::position::{Position, ReadFenError, PositionIssue};
+use crate::record::{GameRecord, GameResult};
+use crate::search::Engine;
+use crate::types::Color;
+
+use std::fmt;
+use std::error;
+
+#[derive(Debug)]
+pub enum MatchError{
+    /// one of the openings isn't valid FEN
+    Fen(ReadFenError),
+    /// one of the openings is a position the engines can't play from,
+    /// with its FEN and what is wrong with it
+    InvalidOpening(String, Vec<PositionIssue>),
+    /// there are no openings to start the games from
+    NoOpenings,
+}
+impl From<ReadFenError> for MatchError{
+    fn from(value: ReadFenError) -> Self{
+        Self::Fen(value)
+    }
+}
+impl fmt::Display for MatchError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::Fen(e) => write!(f, "{e}"),
+            Self::InvalidOpening(fen, issues) => {
+                let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+                write!(f, "Problem encountered while starting match, opening {fen} is invalid: {}", issues.join(", "))
+            },
+            Self::NoOpenings => write!(f, "Problem encountered while starting match, there are no openings"),
+        }
+    }
+}
+impl error::Error for MatchError{}
+
+/// Settings for a match between two engines
+#[derive(Debug, Clone)]
+pub struct MatchConfig{
+    pub games: usize,
+    /// games still going after this many plies are adjudicated as draws
+    pub max_plies: usize,
+    /// starting positions, each one is played twice so both engines get both colors
+    pub openings: Vec<String>,
+}
+impl Default for MatchConfig{
+    fn default() -> Self{
+        MatchConfig{
+            games: 2,
+            max_plies: 200,
+            openings: vec![Position::from_start_position().to_FEN()],
+        }
+    }
+}
+
+/// Reads an openings file, one FEN per line
+///
+/// blank lines and lines starting with '#' are skipped, every
+/// opening has to pass Position::validate
+pub fn read_openings(openings: &str) -> Result<Vec<String>, MatchError>{
+    openings.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|fen| read_opening(fen).map(|p| p.to_FEN()))
+        .collect()
+}
+
+fn read_opening(fen: &str) -> Result<Position, MatchError>{
+    let position = Position::from_FEN(fen)?;
+    position.validate().map_err(|issues| MatchError::InvalidOpening(fen.to_string(), issues))?;
+    Ok(position)
+}
+
+/// Match score from the point of view of the first engine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult{
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+impl MatchResult{
+
+    pub fn games(&self) -> usize{
+        self.wins + self.draws + self.losses
+    }
+
+    /// fraction of the available points scored by the first engine
+    pub fn score(&self) -> f64{
+        (self.wins as f64 + 0.5*self.draws as f64) / self.games() as f64
+    }
+
+    /// Elo difference of the first engine over the second and its
+    /// 95% error margin
+    ///
+    /// None until both engines have scored at least one point,
+    /// the estimate is infinite before then
+    pub fn elo(&self) -> Option<(f64, f64)>{
+
+        let n = self.games() as f64;
+        let p = self.score();
+
+        if self.games() == 0 || p <= 0.0 || p >= 1.0{
+            return None;
+        }
+
+        let variance = (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * (0.0 - p).powi(2)) / n;
+        let deviation = (variance / n).sqrt();
+
+        let low = (p - 1.96*deviation).max(f64::EPSILON);
+        let high = (p + 1.96*deviation).min(1.0 - f64::EPSILON);
+
+        Some((elo_from_score(p), (elo_from_score(high) - elo_from_score(low)) / 2.0))
+    }
+}
+
+fn elo_from_score(p: f64) -> f64{
+    400.0 * (p / (1.0 - p)).log10()
+}
+
+/// Plays a single game from fen, the game is over once the side
+/// to play has no legal moves or max_plies is reached
+///
+/// having no legal moves loses, in check or not, the same as
+/// Position::is_checkmate
+pub fn play_game(white: &mut Engine, black: &mut Engine, fen: &str, max_plies: usize) -> Result<GameRecord, MatchError>{
+
+    let mut position = read_opening(fen)?;
+
+    let mut record = GameRecord{
+        white: white.config().name.clone(),
+        black: black.config().name.clone(),
+        start_fen: position.to_FEN(),
+        moves: Vec::new(),
+        result: GameResult::Draw,
+    };
+
+    while record.moves.len() < max_plies{
+
+        let to_play = position.side_to_move();
+
+        let no_moves = match to_play{
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        };
+
+        if !position.has_legal_move(){
+            record.result = no_moves;
+            return Ok(record);
+        }
+
+        let engine = match to_play{
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+
+        let Some(m) = engine.search(&mut position).best_move else{
+            record.result = no_moves;
+            return Ok(record);
+        };
+
+        position.make_move(m);
+        record.moves.push(m);
+    }
+
+    Ok(record)
+}
+
+/// Plays config.games games between first and second, alternating colors
+/// and cycling through the openings
+///
+/// on_game is called with each finished game, e.g. to write it to a record file
+pub fn play_match<F>(first: &mut Engine, second: &mut Engine, config: &MatchConfig, mut on_game: F) -> Result<MatchResult, MatchError>
+where
+    F: FnMut(usize, &GameRecord),
+{
+    let mut result = MatchResult::default();
+
+    if config.openings.is_empty(){
+        return Err(MatchError::NoOpenings);
+    }
+
+    for game in 0..config.games{
+
+        let fen = &config.openings[(game/2) % config.openings.len()];
+        let first_is_white = game % 2 == 0;
+
+        let record = if first_is_white{
+            play_game(first, second, fen, config.max_plies)?
+        }
+        else{
+            play_game(second, first, fen, config.max_plies)?
+        };
+
+        match (record.result, first_is_white){
+            (GameResult::Draw, _) => result.draws += 1,
+            (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => result.wins += 1,
+            (GameResult::WhiteWins, false) | (GameResult::BlackWins, true) => result.losses += 1,
+        }
+
+        on_game(game, &record);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+    use crate::search::EngineConfig;
+
+    #[test]
+    fn elo_test(){
+
+        let even = MatchResult{wins: 10, draws: 10, losses: 10};
+        let (elo, margin) = even.elo().unwrap();
+        assert!(elo.abs() < 1e-9);
+        assert!(margin > 0.0);
+
+        // 75% is roughly +191
+        let ahead = MatchResult{wins: 15, draws: 0, losses: 5};
+        let (elo, _) = ahead.elo().unwrap();
+        assert!((elo - 190.85).abs() < 0.1);
+
+        assert!(MatchResult{wins: 3, draws: 0, losses: 0}.elo().is_none());
+        assert!(MatchResult::default().elo().is_none());
+    }
+
+    #[test]
+    fn read_openings_test(){
+
+        let openings = read_openings("# comment\n\nunbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1\n k5R1/p7/8/8/7K/8/8/5B2 w 0 - \n").unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[1], "k5R1/p7/8/8/7K/8/8/5B2 w 0 0");
+
+        assert!(matches!(read_openings("not a fen"), Err(MatchError::Fen(_))));
+
+        // no white king
+        assert!(matches!(read_openings("k7/8/8/8/8/8/8/8 w 0 1"), Err(MatchError::InvalidOpening(_, _))));
+    }
+
+    #[test]
+    fn short_match_test(){
+
+        let quick: EngineConfig = "name=quick,depth=1,mobility=0".parse().unwrap();
+        let config = MatchConfig{games: 2, max_plies: 6, ..MatchConfig::default()};
+
+        let mut records = Vec::new();
+        let result = play_match(&mut Engine::new(quick.clone()), &mut Engine::new(quick), &config, |_, r| records.push(r.clone())).unwrap();
+
+        assert_eq!(result.games(), 2);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.moves.len() <= 6));
+
+        // the same engine on both sides plays the same game twice
+        assert_eq!(records[0].moves, records[1].moves);
+
+        let no_openings = MatchConfig{openings: Vec::new(), ..config};
+        let quick: EngineConfig = "depth=1".parse().unwrap();
+        assert!(matches!(play_match(&mut Engine::new(quick.clone()), &mut Engine::new(quick), &no_openings, |_, _| ()), Err(MatchError::NoOpenings)));
+    }
+
+    #[test]
+    fn game_end_test(){
+
+        let quick: EngineConfig = "depth=1".parse().unwrap();
+        let mut white = Engine::new(quick.clone());
+        let mut black = Engine::new(quick);
+
+        // the immobilized black king can't move and loses, even though it isn't attacked
+        let record = play_game(&mut white, &mut black, "k7/1U6/2K5/8/8/8/8/8 b 0 1", 10).unwrap();
+        assert_eq!(record.result, GameResult::WhiteWins);
+        assert!(record.moves.is_empty());
+
+        // and here the white king can take it
+        let record = play_game(&mut white, &mut black, "kU6/1K6/8/8/8/8/8/8 b 0 1", 10).unwrap();
+        assert_eq!(record.result, GameResult::WhiteWins);
+        assert!(record.moves.is_empty());
+
+        assert!(matches!(play_game(&mut white, &mut black, "k7/8/8/8/8/8/8/8 w 0 1", 10), Err(MatchError::InvalidOpening(_, _))));
+    }
+}
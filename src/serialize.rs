@@ -0,0 +1,308 @@
+// serde support for the public types, behind the serde feature
+//
+// human readable formats get the same text used everywhere else in the
+// crate, FEN and the rules for positions and names for squares, other
+// formats get the compact forms. moves are always their u32, the
+// coordinate notation can't be read back without the position the move
+// was made in
+
+use crate::position::{Position, POSITION_BYTES};
+use crate::r#move::{Move, MoveList, MAX_MOVES};
+use crate::rules::Rules;
+use crate::types::{Color, Piece, Square};
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// FEN and the rules if the format is human readable, otherwise
+/// Position::to_bytes, which holds the rules too
+impl Serialize for Position{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        if serializer.is_human_readable(){
+            ReadablePosition{ fen: self.to_FEN(), rules: self.rules() }.serialize(serializer)
+        }
+        else{
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+impl<'de> Deserialize<'de> for Position{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        if deserializer.is_human_readable(){
+            let readable = ReadablePosition::deserialize(deserializer)?;
+            let mut result = Position::from_FEN(&readable.fen).map_err(de::Error::custom)?;
+            result.set_rules(readable.rules);
+            Ok(result)
+        }
+        else{
+            let bytes = deserializer.deserialize_bytes(BytesVisitor)?;
+            Position::from_bytes(&bytes).map_err(de::Error::custom)
+        }
+    }
+}
+
+// FEN has no field for the rules, so they go next to it
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Position")]
+struct ReadablePosition{
+    fen: String,
+    rules: Rules,
+}
+
+// formats differ in whether bytes come back as bytes or a sequence,
+// a sequence is read no further than POSITION_BYTES
+struct BytesVisitor;
+impl<'de> Visitor<'de> for BytesVisitor{
+    type Value = [u8; POSITION_BYTES];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{POSITION_BYTES} bytes written by Position::to_bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E>{
+        v.try_into().map_err(|_| E::invalid_length(v.len(), &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error>{
+        let mut result = [0; POSITION_BYTES];
+        for (i, byte) in result.iter_mut().enumerate(){
+            *byte = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+
+        if seq.next_element::<de::IgnoredAny>()?.is_some(){
+            return Err(de::Error::invalid_length(POSITION_BYTES + 1, &self));
+        }
+        Ok(result)
+    }
+}
+
+/// the form read by its FromStr, e.g. "ultima,chameleon_immobilizes_immobilizer=true"
+impl Serialize for Rules{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for Rules{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Move{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        serializer.serialize_u32(u32::from(*self))
+    }
+}
+impl<'de> Deserialize<'de> for Move{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        Move::try_from(u32::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for MoveList{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        serializer.collect_seq(self.iter())
+    }
+}
+impl<'de> Deserialize<'de> for MoveList{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        deserializer.deserialize_seq(MoveListVisitor)
+    }
+}
+
+// stops at the first move past MAX_MOVES instead of reading them all
+struct MoveListVisitor;
+impl<'de> Visitor<'de> for MoveListVisitor{
+    type Value = MoveList;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "at most {MAX_MOVES} moves")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error>{
+        let mut result = MoveList::new();
+        while let Some(m) = seq.next_element()?{
+            if result.len() == MAX_MOVES{
+                return Err(de::Error::invalid_length(MAX_MOVES + 1, &self));
+            }
+            result.add_move(m);
+        }
+        Ok(result)
+    }
+}
+
+/// the name, e.g. "E2", if the format is human readable, otherwise the index
+impl Serialize for Square{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        if serializer.is_human_readable(){
+            serializer.collect_str(self)
+        }
+        else{
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+}
+impl<'de> Deserialize<'de> for Square{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        if deserializer.is_human_readable(){
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        }
+        else{
+            Square::try_from(u8::deserialize(deserializer)? as usize).map_err(de::Error::custom)
+        }
+    }
+}
+
+/// the white FEN letter, '.' for an empty square
+impl Serialize for Piece{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        serializer.serialize_char(Piece::PIECE_SYMBOLS[*self as usize])
+    }
+}
+impl<'de> Deserialize<'de> for Piece{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        let symbol = char::deserialize(deserializer)?;
+
+        Piece::PIECE_SYMBOLS[..8].iter()
+            .position(|s| *s == symbol)
+            .map(|i| Piece::ALL[i])
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Char(symbol), &"a white FEN piece or '.'"))
+    }
+}
+
+/// 'w' or 'b', as in FEN
+impl Serialize for Color{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        serializer.serialize_char(match self{
+            Color::White => 'w',
+            Color::Black => 'b',
+        })
+    }
+}
+impl<'de> Deserialize<'de> for Color{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+        match char::deserialize(deserializer)?{
+            'w' => Ok(Color::White),
+            'b' => Ok(Color::Black),
+            c => Err(de::Error::invalid_value(de::Unexpected::Char(c), &"'w' or 'b'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+    use serde_test::{assert_tokens, assert_de_tokens_error, Configure, Token};
+
+    // Position has no PartialEq or Debug, go by what it writes instead
+    struct Written(Position);
+    impl fmt::Debug for Written{
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+            write!(f, "{}", self.0.to_FEN())
+        }
+    }
+    impl PartialEq for Written{
+        fn eq(&self, other: &Self) -> bool{
+            self.0.to_bytes() == other.0.to_bytes()
+        }
+    }
+    impl Serialize for Written{
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+            self.0.serialize(serializer)
+        }
+    }
+    impl<'de> Deserialize<'de> for Written{
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>{
+            Position::deserialize(deserializer).map(Written)
+        }
+    }
+
+    #[test]
+    fn position_serde_test(){
+
+        const FEN: &str = "4k3/1p3p2/2bU4/8/8/6B1/3u4/4K3 b 3 7";
+        let position = Position::from_FEN(FEN).unwrap();
+
+        assert_tokens(&Written(position.clone()).readable(), &[
+            Token::Struct{ name: "Position", len: 2 },
+            Token::Str("fen"), Token::Str(FEN),
+            Token::Str("rules"), Token::Str("hyperchess"),
+            Token::StructEnd,
+        ]);
+        assert_de_tokens_error::<serde_test::Readable<Written>>(&[
+            Token::Struct{ name: "Position", len: 2 },
+            Token::Str("fen"), Token::Str("8/8 w 0 1"),
+            Token::Str("rules"), Token::Str("hyperchess"),
+            Token::StructEnd,
+        ], "Problem encountered while reading FEN");
+
+        let read: Position = serde_json::from_str(&serde_json::to_string(&position).unwrap()).unwrap();
+        assert_eq!(read.to_FEN(), FEN);
+
+        // both forms keep the rules
+        let position = Position::from_start_position_with_rules(crate::Rules::ULTIMA);
+
+        let read: Position = serde_json::from_str(&serde_json::to_string(&position).unwrap()).unwrap();
+        assert_eq!(read.rules(), crate::Rules::ULTIMA);
+        assert_eq!(read.zobrist_hash(), position.zobrist_hash());
+
+        let json = format!(r#"{{"fen":"{}","rules":"ultima,springer=true"}}"#, position.to_FEN());
+        assert!(serde_json::from_str::<Position>(&json).is_err());
+
+        let bytes: &'static [u8] = Box::leak(Box::new(position.to_bytes()));
+
+        assert_tokens(&Written(position).compact(), &[Token::Bytes(bytes)]);
+        assert_de_tokens_error::<serde_test::Compact<Written>>(&[Token::Bytes(&bytes[1..])], "invalid length 49, expected 50 bytes written by Position::to_bytes");
+
+        // a sequence of bytes is read no further than its length
+        let mut tokens = vec![Token::Seq{ len: None }];
+        tokens.extend(bytes.iter().map(|b| Token::U8(*b)));
+        tokens.push(Token::U8(0));
+        assert_de_tokens_error::<serde_test::Compact<Written>>(&tokens, "invalid length 51, expected 50 bytes written by Position::to_bytes");
+    }
+
+    #[test]
+    fn move_serde_test(){
+
+        let position = Position::from_start_position();
+        let moves = position.generate_moves();
+        let m = moves[0];
+
+        assert_tokens(&m, &[Token::U32(u32::from(m))]);
+        assert_de_tokens_error::<Move>(&[Token::U32(0)], "Value out of range for conversion");
+
+        let json = serde_json::to_string(&moves).unwrap();
+        let read: MoveList = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.iter().collect::<Vec<_>>(), moves.iter().collect::<Vec<_>>());
+
+        let too_many = format!("[{}]", vec![u32::from(m).to_string(); MAX_MOVES+1].join(","));
+        assert!(serde_json::from_str::<MoveList>(&too_many).is_err());
+
+        // the move past MAX_MOVES is an error, whatever comes after it
+        let mut tokens = vec![Token::Seq{ len: None }];
+        tokens.extend(vec![Token::U32(u32::from(m)); MAX_MOVES + 1]);
+        assert_de_tokens_error::<MoveList>(&tokens, "invalid length 257, expected at most 256 moves");
+    }
+
+    #[test]
+    fn square_piece_rules_color_serde_test(){
+
+        assert_tokens(&Square::E2.readable(), &[Token::Str("E2")]);
+        assert_tokens(&Square::E2.compact(), &[Token::U8(12)]);
+        assert_de_tokens_error::<serde_test::Readable<Square>>(&[Token::Str("E9")], "Problem encountered while reading square");
+
+        assert_tokens(&Piece::Chameleon, &[Token::Char('B')]);
+        assert_tokens(&Piece::Empty, &[Token::Char('.')]);
+        assert_de_tokens_error::<Piece>(&[Token::Char('b')], "invalid value: character `b`, expected a white FEN piece or '.'");
+
+        assert_tokens(&crate::Rules::BAROQUE, &[Token::Str("baroque")]);
+        assert_de_tokens_error::<crate::Rules>(&[Token::Str("chess")], "Problem encountered while reading rules");
+
+        assert_tokens(&Color::Black, &[Token::Char('b')]);
+        assert_de_tokens_error::<Color>(&[Token::Char('x')], "invalid value: character `x`, expected 'w' or 'b'");
+    }
+}
@@ -0,0 +1,759 @@
+use crate::position::Position;
+use crate::tables::get_king_moves;
+use crate::types::{Bitboard, Color, Piece, Square};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::error;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+const MAGIC: &[u8; 8] = b"HMGTB002";
+
+/// Largest number of pieces (kings included) a table can be generated for
+pub const MAX_PIECES: usize = 4;
+
+// an entry is 2 bits of outcome followed by 14 bits of distance to mate
+const ILLEGAL: u16 = 0;
+const DRAW: u16 = 1 << 14;
+const WIN: u16 = 2 << 14;
+const LOSS: u16 = 3 << 14;
+const KIND_MASK: u16 = 3 << 14;
+const DTM_MASK: u16 = (1 << 14) - 1;
+
+// set in the move counts of retrograde while every capture found so far
+// leads to a win for the opponent. MAX_PIECES keeps the counts below it
+const CAN_LOSE: u8 = 1 << 7;
+
+// number of (white king, black king) placements with the white king on an
+// earlier square, the kings can't share a square or stand next to each other
+static KING_PAIR_OFFSETS: LazyLock<[usize; 65]> = LazyLock::new(|| {
+    let mut result = [0; 65];
+    for square in Square::ALL{
+        result[square as usize + 1] = result[square as usize] + 63 - get_king_moves(square).count() as usize;
+    }
+    result
+});
+
+/// n choose k, small enough for every table
+const fn choose(n: usize, k: usize) -> usize{
+    if k > n{
+        return 0;
+    }
+    let mut result = 1;
+    let mut i = 0;
+    while i < k{
+        result = result * (n - i) / (i + 1);
+        i += 1;
+    }
+    result
+}
+
+/// number of squares before square that aren't in occupied
+fn free_rank(square: Square, occupied: Bitboard) -> usize{
+    square as usize - (occupied & Bitboard((1 << square as usize) - 1)).count() as usize
+}
+
+/// the square with rank free squares before it
+fn nth_free(rank: usize, occupied: Bitboard) -> Square{
+    (!occupied).into_iter().nth(rank).unwrap()
+}
+
+// order pieces are listed in, strongest first as in "KQvK" for chess
+const PIECE_ORDER: [Piece; 7] = [
+    Piece::King,
+    Piece::Immobilizer,
+    Piece::Retractor,
+    Piece::Chameleon,
+    Piece::Springer,
+    Piece::Coordinator,
+    Piece::Stradler,
+];
+
+#[derive(Debug)]
+pub struct ParseMaterialError{}
+impl fmt::Display for ParseMaterialError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading material")
+    }
+}
+impl error::Error for ParseMaterialError{}
+
+#[derive(Debug)]
+pub struct ReadTablebaseError{}
+impl From<io::Error> for ReadTablebaseError{
+    fn from(_value: io::Error) -> Self{
+        ReadTablebaseError{}
+    }
+}
+impl fmt::Display for ReadTablebaseError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading tablebase")
+    }
+}
+impl error::Error for ReadTablebaseError{}
+
+/// Value of a position under perfect play, from the point of view of the side to play
+///
+/// distances are the number of plies until the losing side is checkmated,
+/// Loss(0) means the side to play is checkmated already. as in
+/// Position::is_checkmate, having no legal moves at all counts as checkmate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablebaseValue{
+    Win(u16),
+    Draw,
+    Loss(u16),
+}
+impl TablebaseValue{
+    fn from_entry(entry: u16) -> Option<Self>{
+        match entry & KIND_MASK{
+            DRAW => Some(Self::Draw),
+            WIN => Some(Self::Win(entry & DTM_MASK)),
+            LOSS => Some(Self::Loss(entry & DTM_MASK)),
+            _ => None,
+        }
+    }
+}
+
+/// The pieces on the board, written as the white pieces then the black
+/// pieces separated by a 'v', e.g. "KUvK" or "KRPvKP"
+///
+/// both sides always have exactly one king
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Material{
+    counts: [u8; 16],
+}
+impl Material{
+
+    pub(crate) fn of(position: &Position) -> Self{
+
+        let mut counts = [0; 16];
+
+        for c in [Color::White, Color::Black]{
+            for p in &(Piece::ALL)[1..]{
+                counts[c | *p] = position.pieces(c, *p).0.count_ones() as u8;
+            }
+        }
+
+        Material{ counts }
+    }
+
+    /// number of pieces, kings included
+    pub fn len(&self) -> usize{
+        self.counts.iter().map(|c| *c as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    /// every piece other than the kings in the canonical order, as
+    /// (color, piece, how many there are)
+    fn groups(&self) -> Vec<(Color, Piece, usize)>{
+        let mut result = Vec::new();
+        for c in [Color::White, Color::Black]{
+            for p in &PIECE_ORDER[1..]{
+                if self.counts[c | *p] != 0{
+                    result.push((c, *p, self.counts[c | *p] as usize));
+                }
+            }
+        }
+        result
+    }
+
+    /// number of indices in the table for this material
+    fn table_size(&self) -> usize{
+
+        let mut result = 2 * KING_PAIR_OFFSETS[64];
+        let mut free = 62;
+
+        for (_, _, count) in self.groups(){
+            result *= choose(free, count);
+            free -= count;
+        }
+
+        result
+    }
+
+    /// materials reachable by capturing a single piece other than a king
+    fn captures(&self) -> Vec<Material>{
+        (0..16)
+            .filter(|i| self.counts[*i] != 0 && *i != Color::White | Piece::King && *i != Color::Black | Piece::King)
+            .map(|i| {
+                let mut counts = self.counts;
+                counts[i] -= 1;
+                Material{ counts }
+            })
+            .collect()
+    }
+}
+impl FromStr for Material{
+    type Err = ParseMaterialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+
+        let (white, black) = s.trim().split_once(['v', 'V']).ok_or(ParseMaterialError{})?;
+        let mut counts = [0; 16];
+
+        for (color, pieces) in [(Color::White, white), (Color::Black, black)]{
+            for symbol in pieces.chars(){
+                let piece = PIECE_ORDER.into_iter()
+                    .find(|p| Piece::PIECE_SYMBOLS[*p as usize] == symbol.to_ascii_uppercase())
+                    .ok_or(ParseMaterialError{})?;
+                counts[color | piece] += 1;
+            }
+        }
+
+        let result = Material{ counts };
+
+        if counts[Color::White | Piece::King] != 1 || counts[Color::Black | Piece::King] != 1 || result.len() > MAX_PIECES{
+            return Err(ParseMaterialError{});
+        }
+
+        Ok(result)
+    }
+}
+impl fmt::Display for Material{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in [Color::White, Color::Black].into_iter().enumerate(){
+            if i == 1{
+                write!(f, "v")?;
+            }
+            for p in PIECE_ORDER{
+                for _ in 0..self.counts[c | p]{
+                    write!(f, "{}", Piece::PIECE_SYMBOLS[p as usize])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every position of one material set
+///
+/// positions are numbered by placing the kings first, counting only the
+/// pairs of squares they can stand on, then each group of identical pieces
+/// as one choice of k of the squares still free. the side to play is the
+/// most significant digit, so every index is a distinct placement and each
+/// placement has exactly one index
+pub struct Tablebase{
+    material: Material,
+    groups: Vec<(Color, Piece, usize)>,
+    entries: Vec<u16>,
+}
+impl Tablebase{
+
+    fn new(material: Material, entries: Vec<u16>) -> Self{
+        Tablebase{
+            material,
+            groups: material.groups(),
+            entries,
+        }
+    }
+
+    pub fn material(&self) -> Material{
+        self.material
+    }
+
+    /// number of indices, including ones that aren't legal positions
+    pub fn len(&self) -> usize{
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.entries.is_empty()
+    }
+
+    /// values of every legal position in the table
+    pub fn values(&self) -> impl Iterator<Item = TablebaseValue> + '_{
+        self.entries.iter().filter_map(|e| TablebaseValue::from_entry(*e))
+    }
+
+    /// None if position has different material or isn't legal
+    pub fn probe(&self, position: &Position) -> Option<TablebaseValue>{
+        TablebaseValue::from_entry(self.entries[self.index(position)?])
+    }
+
+    fn index(&self, position: &Position) -> Option<usize>{
+
+        if Material::of(position) != self.material{
+            return None;
+        }
+
+        let white_king = position.pieces(Color::White, Piece::King).bitscanforward_square();
+        let black_king = position.pieces(Color::Black, Piece::King).bitscanforward_square();
+
+        let mut occupied = get_king_moves(white_king) | Bitboard::from(white_king);
+        if occupied.contains(black_king){
+            return None;
+        }
+
+        let mut result = KING_PAIR_OFFSETS[white_king as usize] + free_rank(black_king, occupied);
+        let mut free = 62;
+
+        occupied = Bitboard::from(white_king) | Bitboard::from(black_king);
+
+        for (color, piece, count) in &self.groups{
+
+            // squares come out in increasing order
+            let squares = position.pieces(*color, *piece);
+            let rank: usize = squares.into_iter().enumerate()
+                .map(|(i, s)| choose(free_rank(s, occupied), i+1))
+                .sum();
+
+            result = result * choose(free, *count) + rank;
+            free -= count;
+            occupied |= squares;
+        }
+
+        if position.side_to_move() == Color::Black{
+            result += self.len() / 2;
+        }
+
+        Some(result)
+    }
+
+    fn decode(&self, index: usize) -> Position{
+
+        let half = self.len() / 2;
+        let to_play = if index >= half {Color::Black} else {Color::White};
+        let mut rest = index % half;
+
+        // the last group is the least significant digit
+        let mut free = 62 - self.groups.iter().map(|(_, _, count)| count).sum::<usize>();
+        let mut ranks = vec![0; self.groups.len()];
+        for (i, (_, _, count)) in self.groups.iter().enumerate().rev(){
+            free += count;
+            ranks[i] = rest % choose(free, *count);
+            rest /= choose(free, *count);
+        }
+
+        let white_king = Square::ALL[KING_PAIR_OFFSETS.partition_point(|offset| *offset <= rest) - 1];
+        let occupied = get_king_moves(white_king) | Bitboard::from(white_king);
+        let black_king = nth_free(rest - KING_PAIR_OFFSETS[white_king as usize], occupied);
+
+        let mut placed = vec![(Color::White, Piece::King, white_king), (Color::Black, Piece::King, black_king)];
+        let mut occupied = Bitboard::from(white_king) | Bitboard::from(black_king);
+
+        for ((color, piece, count), mut rank) in self.groups.iter().zip(ranks){
+
+            let mut squares = Bitboard::EMPTY;
+            for k in (1..=*count).rev(){
+                // the largest c with c choose k no more than what is left
+                let mut c = k - 1;
+                while choose(c + 1, k) <= rank{
+                    c += 1;
+                }
+                rank -= choose(c, k);
+                squares |= Bitboard::from(nth_free(c, occupied));
+            }
+
+            placed.extend(squares.into_iter().map(|s| (*color, *piece, s)));
+            occupied |= squares;
+        }
+
+        Position::from_pieces(to_play, &placed)
+    }
+
+    /// Indices of the positions one quiet move before index
+    fn predecessors(&self, index: usize) -> Vec<usize>{
+
+        let position = self.decode(index);
+
+        position.generate_quiet_unmoves().into_iter()
+            .filter_map(|m| {
+                let mut before = position.clone();
+                before.unmake_move(m);
+                self.index(&before)
+            })
+            .filter(|before| self.entries[*before] != ILLEGAL)
+            .collect()
+    }
+}
+
+fn schedule(buckets: &mut Vec<Vec<(usize, u16)>>, index: usize, entry: u16){
+    let distance = (entry & DTM_MASK) as usize;
+    assert!(distance < DTM_MASK as usize, "distance to mate doesn't fit in an entry");
+    if buckets.len() <= distance{
+        buckets.resize_with(distance + 1, Vec::new);
+    }
+    buckets[distance].push((index, entry));
+}
+
+/// A collection of tablebases that can be generated, saved and probed
+///
+/// on disk it is the 8 byte magic "HMGTB002" followed by each table as
+/// its material (length as one byte then the text), the number of
+/// entries (u64) and the 16 bit entries, all little endian
+pub struct TablebaseSet{
+    tables: HashMap<Material, Tablebase>,
+}
+impl TablebaseSet{
+
+    pub fn new() -> Self{
+        TablebaseSet{
+            tables: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize{
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.tables.is_empty()
+    }
+
+    pub fn get(&self, material: &Material) -> Option<&Tablebase>{
+        self.tables.get(material)
+    }
+
+    /// every table in the set, smallest material first
+    pub fn tables(&self) -> Vec<&Tablebase>{
+        let mut result: Vec<&Tablebase> = self.tables.values().collect();
+        result.sort_by_key(|t| (t.material.len(), t.material.to_string()));
+        result
+    }
+
+    /// None unless the set has a table for the material of position
+    pub fn probe(&self, position: &Position) -> Option<TablebaseValue>{
+        self.tables.get(&Material::of(position))?.probe(position)
+    }
+
+    /// Generates the table for material, along with every table
+    /// a capture from it can reach that isn't in the set yet
+    pub fn generate(&mut self, material: Material){
+
+        if self.tables.contains_key(&material){
+            return;
+        }
+
+        for smaller in material.captures(){
+            self.generate(smaller);
+        }
+
+        let table = self.retrograde(material);
+        self.tables.insert(material, table);
+    }
+
+    /// Retrograde analysis of a single table, every table captures lead to must already be in the set
+    ///
+    /// positions are resolved in order of distance to mate, starting from
+    /// the checkmates. a resolved loss makes every position that can move
+    /// into it a win, a resolved win takes one move away from each position
+    /// that can move into it, and positions that run out of moves are lost.
+    /// captures leave the table so their values are read from the smaller tables up front
+    fn retrograde(&self, material: Material) -> Tablebase{
+
+        let size = material.table_size();
+        let mut table = Tablebase::new(material, vec![ILLEGAL; size]);
+
+        // quiet moves into positions that aren't known to be won for the
+        // opponent, along with CAN_LOSE
+        let mut remaining: Vec<u8> = vec![CAN_LOSE; size];
+
+        // unresolved positions are kept as DRAW, with the distance captures
+        // into won positions put off a loss by in the distance bits. a
+        // position is resolved once its entry is a WIN or LOSS
+
+        // positions waiting to be resolved, by distance to mate
+        let mut buckets: Vec<Vec<(usize, u16)>> = Vec::new();
+
+        for (index, count) in remaining.iter_mut().enumerate(){
+
+            let mut position = table.decode(index);
+
+            // the side to play could take the king
+            if position.is_attacking_king(){
+                continue;
+            }
+
+            let mut entry = DRAW;
+            let mut any_legal = false;
+
+            for m in position.generate_moves(){
+
+                if !position.is_move_legal(m){
+                    continue;
+                }
+                any_legal = true;
+
+                if !m.is_capture(){
+                    *count += 1;
+                    continue;
+                }
+
+                position.make_move(m);
+                let value = self.probe(&position).expect("capture left a material without a table");
+                position.unmake_move(m);
+
+                match value{
+                    TablebaseValue::Loss(d) => {
+                        *count &= !CAN_LOSE;
+                        schedule(&mut buckets, index, WIN | (d+1));
+                    },
+                    TablebaseValue::Win(d) => entry = DRAW | (entry & DTM_MASK).max(d+1),
+                    TablebaseValue::Draw => *count &= !CAN_LOSE,
+                }
+            }
+
+            table.entries[index] = entry;
+
+            if !any_legal{
+                schedule(&mut buckets, index, LOSS);
+            }
+            else if *count == CAN_LOSE{
+                schedule(&mut buckets, index, LOSS | (entry & DTM_MASK));
+            }
+        }
+
+        let resolved = |entry: u16| entry & KIND_MASK == WIN || entry & KIND_MASK == LOSS;
+
+        let mut distance = 0;
+        while distance < buckets.len(){
+
+            for (index, entry) in std::mem::take(&mut buckets[distance]){
+
+                if resolved(table.entries[index]){
+                    continue;
+                }
+                table.entries[index] = entry;
+
+                for before in table.predecessors(index){
+
+                    if resolved(table.entries[before]){
+                        continue;
+                    }
+
+                    let next = distance as u16 + 1;
+
+                    if entry & KIND_MASK == LOSS{
+                        schedule(&mut buckets, before, WIN | next);
+                    }
+                    else{
+                        remaining[before] -= 1;
+                        if remaining[before] == CAN_LOSE{
+                            schedule(&mut buckets, before, LOSS | next.max(table.entries[before] & DTM_MASK));
+                        }
+                    }
+                }
+            }
+
+            distance += 1;
+        }
+
+        // anything left over can avoid losing forever
+        for entry in table.entries.iter_mut(){
+            if *entry & KIND_MASK == DRAW{
+                *entry = DRAW;
+            }
+        }
+
+        table
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()>{
+
+        writer.write_all(MAGIC)?;
+
+        for table in self.tables(){
+            let material = table.material.to_string();
+            writer.write_all(&[material.len() as u8])?;
+            writer.write_all(material.as_bytes())?;
+            writer.write_all(&(table.entries.len() as u64).to_le_bytes())?;
+
+            let bytes: Vec<u8> = table.entries.iter().flat_map(|e| e.to_le_bytes()).collect();
+            writer.write_all(&bytes)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, ReadTablebaseError>{
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC{
+            return Err(ReadTablebaseError{});
+        }
+
+        let mut result = Self::new();
+
+        loop{
+            let mut length = [0; 1];
+            if reader.read(&mut length)? == 0{
+                break;
+            }
+
+            let mut material = vec![0; length[0] as usize];
+            reader.read_exact(&mut material)?;
+            let material: Material = String::from_utf8(material).ok()
+                .and_then(|m| m.parse().ok())
+                .ok_or(ReadTablebaseError{})?;
+
+            let size = material.table_size();
+
+            let mut count = [0; 8];
+            reader.read_exact(&mut count)?;
+            if u64::from_le_bytes(count) != size as u64{
+                return Err(ReadTablebaseError{});
+            }
+
+            let mut bytes = vec![0; 2 * size];
+            reader.read_exact(&mut bytes)?;
+            let entries = bytes.chunks_exact(2).map(|e| u16::from_le_bytes([e[0], e[1]])).collect();
+
+            result.tables.insert(material, Tablebase::new(material, entries));
+        }
+
+        Ok(result)
+    }
+}
+impl Default for TablebaseSet{
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    /// checks every legal position in the table agrees with the values of its children
+    fn check_table(set: &TablebaseSet, material: Material){
+
+        let table = set.get(&material).unwrap();
+
+        for index in 0..table.len(){
+
+            let Some(value) = TablebaseValue::from_entry(table.entries[index]) else{
+                continue;
+            };
+
+            let mut position = table.decode(index);
+            assert_eq!(table.index(&position), Some(index));
+
+            let mut children = Vec::new();
+            for m in position.generate_moves(){
+                if position.is_move_legal(m){
+                    position.make_move(m);
+                    children.push(set.probe(&position).unwrap());
+                    position.unmake_move(m);
+                }
+            }
+
+            let fastest_win = children.iter().filter_map(|c| match c {TablebaseValue::Loss(d) => Some(*d), _ => None}).min();
+            let slowest_loss = children.iter().filter_map(|c| match c {TablebaseValue::Win(d) => Some(*d), _ => None}).max();
+            let all_lose = children.iter().all(|c| matches!(c, TablebaseValue::Win(_)));
+
+            let expected = match (fastest_win, all_lose){
+                (Some(d), _) => TablebaseValue::Win(d+1),
+                (None, true) => TablebaseValue::Loss(slowest_loss.map_or(0, |d| d+1)),
+                (None, false) => TablebaseValue::Draw,
+            };
+
+            assert_eq!(value, expected, "{}", position.to_FEN());
+        }
+    }
+
+    #[test]
+    fn material_test(){
+
+        let material: Material = "kqvk".parse().unwrap();
+        assert_eq!(material.to_string(), "KQvK");
+        assert_eq!(material.len(), 3);
+        assert!("KPRUvK".parse::<Material>().is_err());
+        assert_eq!("KPNvK".parse::<Material>().unwrap().to_string(), "KNPvK");
+
+        assert!("KQ".parse::<Material>().is_err());
+        assert!("QvK".parse::<Material>().is_err());
+        assert!("KKvK".parse::<Material>().is_err());
+        assert!("KXvK".parse::<Material>().is_err());
+    }
+
+    #[test]
+    fn index_test(){
+
+        // two identical stradlers are one choice of 2 of the 62 squares left by the kings
+        let material: Material = "KPPvK".parse().unwrap();
+        assert_eq!(material.table_size(), 2 * 3612 * 1891);
+
+        let table = Tablebase::new(material, vec![ILLEGAL; material.table_size()]);
+        for index in (0..table.len()).step_by(997).chain([0, table.len()/2 - 1, table.len() - 1]){
+            let position = table.decode(index);
+            assert!(position.is_consistent());
+            assert_eq!(table.index(&position), Some(index), "{}", position.to_FEN());
+        }
+
+        let position = Position::from_FEN("8/8/8/8/8/8/8/kK6 w 0 1").unwrap();
+        assert_eq!(TablebaseSet::new().retrograde("KvK".parse().unwrap()).index(&position), None);
+    }
+
+    #[test]
+    fn king_vs_king_test(){
+
+        let material: Material = "KvK".parse().unwrap();
+        let mut set = TablebaseSet::new();
+        set.generate(material);
+
+        // kings can't stand next to each other, so every index is a legal position
+        let table = set.get(&material).unwrap();
+        assert_eq!(table.len(), 2 * (64*63 - 420));
+        assert_eq!(table.values().count(), table.len());
+        assert!(table.values().all(|v| v == TablebaseValue::Draw));
+        check_table(&set, material);
+
+        let position = Position::from_FEN("8/8/3k4/8/8/3K4/8/8 b 0 1").unwrap();
+        assert_eq!(set.probe(&position), Some(TablebaseValue::Draw));
+        assert_eq!(set.probe(&Position::from_start_position()), None);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        set.write_to(&mut bytes).unwrap();
+        let read = TablebaseSet::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.probe(&position), Some(TablebaseValue::Draw));
+        assert!(read.get(&material).unwrap().entries == table.entries);
+
+        assert!(TablebaseSet::read_from(&mut &bytes[..100]).is_err());
+        assert!(TablebaseSet::read_from(&mut &b"not a tablebase"[..]).is_err());
+    }
+
+    // building a three piece table takes minutes without optimizations
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "builds the whole KQvK table, run with --release")]
+    fn retrograde_test(){
+
+        let material: Material = "KQvK".parse().unwrap();
+        let mut set = TablebaseSet::new();
+        set.generate(material);
+
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get(&material).unwrap().len(), 2 * 3612 * 62);
+
+        // the retractor going to b3 leaves the king on a1 without a move, it
+        // would be taken on a2 or b2 by the retractor moving away and on b1
+        // or b2 by the king. a1 isn't attacked, running out of moves is enough
+        let position = Position::from_FEN("8/8/8/8/8/8/8/k1KQ4 w 0 1").unwrap();
+        assert_eq!(set.probe(&position), Some(TablebaseValue::Win(1)));
+        let position = Position::from_FEN("8/8/8/8/8/1Q6/8/k1K5 b 0 1").unwrap();
+        assert_eq!(set.probe(&position), Some(TablebaseValue::Loss(0)));
+
+        // taking the retractor leaves a drawn KvK
+        let position = Position::from_FEN("8/8/8/8/8/8/8/kQ1K4 b 0 1").unwrap();
+        assert_eq!(set.probe(&position), Some(TablebaseValue::Draw));
+    }
+
+    // every one of the 447,888 indices with its children, run with
+    // cargo test --release -- --ignored
+    #[test]
+    #[ignore = "checks the whole KQvK table against itself"]
+    fn retrograde_sweep_test(){
+
+        let material: Material = "KQvK".parse().unwrap();
+        let mut set = TablebaseSet::new();
+        set.generate(material);
+        check_table(&set, material);
+    }
+}
@@ -1,35 +1,66 @@
 use crate::types::{Bitboard, Square};
+use crate::utils::lookup_table;
+
+#[cfg(feature = "compact-tables")]
+pub use crate::compact::{
+    get_orth_moves,
+    get_diag_moves,
+    get_potential_stradler_captures,
+    get_death_squares,
+    get_springer_landing_square,
+    get_springer_captured_square,
+    get_retractor_lookup,
+};
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_orth_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    #[cfg(feature = "pext")]
+    if crate::pext::bmi2_available(){
+        return crate::pext::get_orth_moves(square, total_board);
+    }
     ORTH_LOOKUPS[magic_index(total_board & ORTH_RELEVANT_BLOCKERS[square], ORTH_MAGICS[square], ORTH_THROWAWAY[square]) + ORTH_OFFSETS[square]]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_diag_moves(square: Square, total_board: Bitboard) -> Bitboard{
+    #[cfg(feature = "pext")]
+    if crate::pext::bmi2_available(){
+        return crate::pext::get_diag_moves(square, total_board);
+    }
     DIAG_LOOKUPS[magic_index(total_board & DIAG_RELEVANT_BLOCKERS[square], DIAG_MAGICS[square], DIAG_THROWAWAY[square]) + DIAG_OFFSETS[square]]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_potential_stradler_captures(square: Square, friendly_stradlers: Bitboard) -> [Bitboard; 4]{
+    #[cfg(feature = "pext")]
+    if crate::pext::bmi2_available(){
+        return crate::pext::get_potential_stradler_captures(square, friendly_stradlers);
+    }
     STRADLER_LOOKUPS[magic_index(friendly_stradlers & RELEVANT_BUDDIES[square], STRADLER_MAGICS[square], STRADLER_THROWAWAY[square]) + STRADLER_OFFSETS[square]]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_death_squares(s1: Square, s2: Square) -> [Bitboard; 2]{
     DEATH_SQUARE_LOOKUP[s1][s2]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_springer_landing_square(start: Square, capturing: Square) -> Bitboard{
     SPRINGER_LANDING_LOOKUP[start][capturing]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_springer_captured_square(start: Square, landing: Square) -> Bitboard{
     SPRINGER_CAPTURED_LOOKUP[start][landing]
 }
 
+#[cfg(not(feature = "compact-tables"))]
 #[inline]
 pub fn get_retractor_lookup(start: Square, moving_to: Square) -> Bitboard{
     RETRACTOR_LOOKUP[start][moving_to]
@@ -89,8 +120,8 @@ const fn initialize_num_to_edge() -> [[i8; 8]; 64]{
 }
 
 // n, e, s, w, ne, se, sw, nw
-static NUM_SQUARES_TO_EDGE: [[i8; 8]; 64] = initialize_num_to_edge();
-static DIR_OFFSETS: [i8; 8] = [8, 1, -8, -1, 9, -7, -9, 7];
+pub(crate) static NUM_SQUARES_TO_EDGE: [[i8; 8]; 64] = initialize_num_to_edge();
+pub(crate) static DIR_OFFSETS: [i8; 8] = [8, 1, -8, -1, 9, -7, -9, 7];
 static OPPOSITE_INDICES: [usize; 8] = [2, 3, 0, 1, 6, 7, 4, 5];
 
 const fn create_orthogonal_block_masks() -> [Bitboard; 64]{
@@ -457,6 +488,7 @@ pub const fn generate_springer_captured(start: Square, relevant_blockers: Bitboa
     result
 }
 
+#[cfg(any(test, not(feature = "compact-tables")))]
 const fn generate_death_squares(mut king_coord_bitboard: Bitboard) -> Bitboard{
 
     let s1 = king_coord_bitboard.pop_lsb();
@@ -652,6 +684,12 @@ pub static RETRACTOR_THROWAWAY: [u8; 64] = {
     result
 };
 
+// sums of 1 << (64-throwaway) over every square, these change along with
+// the magics and throwaways (see magic::MagicTable::write_source)
+pub const ORTH_LOOKUP_SIZE: usize = 102400;
+pub const DIAG_LOOKUP_SIZE: usize = 5248;
+pub const STRADLER_LOOKUP_SIZE: usize = 576;
+
 pub static ORTH_OFFSETS: [usize; 64] = generate_orthogonal_offsets();
 
 pub static DIAG_OFFSETS: [usize; 64] = generate_diagonal_offsets();
@@ -660,8 +698,9 @@ pub static STRADLER_OFFSETS: [usize; 64] = generate_buddy_offsets();
 
 pub static RETRACTOR_OFFSETS: [usize; 64] = generate_retractor_offsets();
 
-pub static ORTH_LOOKUPS: [Bitboard; 102400] = {
-    let mut result: [Bitboard; 102400] = [Bitboard::UNUSED; 102400];
+lookup_table!{
+pub static ORTH_LOOKUPS: [Bitboard; ORTH_LOOKUP_SIZE] = {
+    let mut result: [Bitboard; ORTH_LOOKUP_SIZE] = [Bitboard::UNUSED; ORTH_LOOKUP_SIZE];
 
     let mut i = 0;
     while i < 64{
@@ -671,7 +710,7 @@ pub static ORTH_LOOKUPS: [Bitboard; 102400] = {
         let mut blocker_subset: Bitboard = Bitboard::EMPTY;
 
         let mut j = 0;
-        while j < (1 << (64-ORTH_THROWAWAY[i])){
+        while j < (1 << relevant_blockers.0.count_ones()){
             blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
 
             let index = magic_index(blocker_subset, square_magic, ORTH_THROWAWAY[i]);
@@ -686,9 +725,11 @@ pub static ORTH_LOOKUPS: [Bitboard; 102400] = {
 
     result
 };
+}
 
-pub static DIAG_LOOKUPS: [Bitboard; 5248] = {
-    let mut result: [Bitboard; 5248] = [Bitboard::UNUSED; 5248];
+lookup_table!{
+pub static DIAG_LOOKUPS: [Bitboard; DIAG_LOOKUP_SIZE] = {
+    let mut result: [Bitboard; DIAG_LOOKUP_SIZE] = [Bitboard::UNUSED; DIAG_LOOKUP_SIZE];
 
     let mut i = 0;
     while i < 64{
@@ -698,7 +739,7 @@ pub static DIAG_LOOKUPS: [Bitboard; 5248] = {
         let mut blocker_subset: Bitboard = Bitboard::EMPTY;
 
         let mut j = 0;
-        while j < (1 << (64-DIAG_THROWAWAY[i])){
+        while j < (1 << relevant_blockers.0.count_ones()){
             blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
 
             let index = magic_index(blocker_subset, square_magic, DIAG_THROWAWAY[i]);
@@ -713,9 +754,11 @@ pub static DIAG_LOOKUPS: [Bitboard; 5248] = {
 
     result
 };
+}
 
-pub static STRADLER_LOOKUPS: [[Bitboard; 4]; 576] = {
-    let mut result: [[Bitboard; 4]; 576] = [[Bitboard::UNUSED; 4]; 576];
+lookup_table!{
+pub static STRADLER_LOOKUPS: [[Bitboard; 4]; STRADLER_LOOKUP_SIZE] = {
+    let mut result: [[Bitboard; 4]; STRADLER_LOOKUP_SIZE] = [[Bitboard::UNUSED; 4]; STRADLER_LOOKUP_SIZE];
 
     let mut i = 0;
     while i < 64{
@@ -725,7 +768,7 @@ pub static STRADLER_LOOKUPS: [[Bitboard; 4]; 576] = {
         let mut blocker_subset: Bitboard = Bitboard::EMPTY;
 
         let mut j = 0;
-        while j < (1 << (64-STRADLER_THROWAWAY[i])){
+        while j < (1 << relevant_blockers.0.count_ones()){
             blocker_subset.0 = blocker_subset.0.wrapping_sub(relevant_blockers.0) & relevant_blockers.0;
 
             let index = magic_index(blocker_subset, square_magic, STRADLER_THROWAWAY[i]);
@@ -740,6 +783,7 @@ pub static STRADLER_LOOKUPS: [[Bitboard; 4]; 576] = {
 
     result
 };
+}
 
 /*
 pub static RETRACTOR_LOOKUPS: [Bitboard; 10016] = {
@@ -799,6 +843,7 @@ pub static RETRACTOR_CAPTURED: [Bitboard; 10016] = {
 };
 */
 
+lookup_table!{
 static RETRACTOR_LOOKUP: [[Bitboard; 64]; 64] = {
 
     let mut result: [[Bitboard; 64]; 64] = [[Bitboard::EMPTY; 64]; 64];
@@ -821,8 +866,10 @@ static RETRACTOR_LOOKUP: [[Bitboard; 64]; 64] = {
 
     result
 };
+}
 
 // [springer position][position of captured piece]
+lookup_table!{
 pub static SPRINGER_LANDING_LOOKUP: [[Bitboard; 64]; 64] = {
     let mut result: [[Bitboard; 64]; 64] = [[Bitboard::EMPTY; 64]; 64];
 
@@ -846,8 +893,10 @@ pub static SPRINGER_LANDING_LOOKUP: [[Bitboard; 64]; 64] = {
 
     result
 };
+}
 
 // [springer start position][springer landing square]
+lookup_table!{
 pub static SPRINGER_CAPTURED_LOOKUP: [[Bitboard; 64]; 64] = {
     let mut result: [[Bitboard; 64]; 64] = [[Bitboard::EMPTY; 64]; 64];
 
@@ -872,7 +921,9 @@ pub static SPRINGER_CAPTURED_LOOKUP: [[Bitboard; 64]; 64] = {
 
     result
 };
+}
 
+lookup_table!{
 pub static DEATH_SQUARE_LOOKUP: [[[Bitboard; 2]; 64]; 64] = {
     let mut result: [[[Bitboard; 2]; 64]; 64] = [[[Bitboard::EMPTY; 2]; 64]; 64];
 
@@ -901,6 +952,7 @@ pub static DEATH_SQUARE_LOOKUP: [[[Bitboard; 2]; 64]; 64] = {
 
     result
 };
+}
 
 #[cfg(test)]
 mod test{
@@ -908,6 +960,7 @@ mod test{
 
     use super::*;
 
+    #[cfg(not(feature = "compact-tables"))]
     static potential_orth_blockers: [Bitboard; 64] = {
         let mut result: [Bitboard; 64] = [Bitboard::EMPTY; 64];
 
@@ -919,6 +972,7 @@ mod test{
         result
     };
 
+    #[cfg(not(feature = "compact-tables"))]
     static potential_diag_blockers: [Bitboard; 64] = {
         let mut result: [Bitboard; 64] = [Bitboard::EMPTY; 64];
 
@@ -939,6 +993,7 @@ mod test{
     }
 
     #[test]
+    #[cfg(not(feature = "compact-tables"))]
     fn orth_lookup_test(){
         for square in Square::ALL{
 
@@ -965,6 +1020,7 @@ mod test{
     }
 
     #[test]
+    #[cfg(not(feature = "compact-tables"))]
     fn diag_lookup_test(){
         for square in Square::ALL{
             let square_magic = DIAG_MAGICS[square];
@@ -987,6 +1043,7 @@ mod test{
     }
 
     #[test]
+    #[cfg(not(feature = "compact-tables"))]
     fn stradler_capture_test(){
         for square in Square::ALL{
             let square_magic = STRADLER_MAGICS[square];
@@ -1040,6 +1097,7 @@ mod test{
     }
 
     #[test]
+    #[cfg(not(feature = "compact-tables"))]
     fn springer_capture_test(){
         
         for square in Square::ALL{
@@ -2,10 +2,17 @@ use crate::utils::*;
 
 use std::ops::{BitAnd, BitOr, BitXor, BitAndAssign, BitOrAssign, BitXorAssign, Not};
 use std::fmt;
-use std::mem::transmute;
+use std::error;
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct ConversionError{}
+impl fmt::Display for ConversionError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value out of range for conversion")
+    }
+}
+impl error::Error for ConversionError{}
 
 /// wrapper struct around u64 to represent
 /// bitboard
@@ -63,6 +70,20 @@ impl fmt::Debug for Bitboard{
         Ok(())
     }
 }
+// like Debug but with the ranks and files labelled
+impl fmt::Display for Bitboard{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev(){
+            write!(f, "{}", rank+1)?;
+            for file in 0..8{
+                let to_write = if (self.0 >> (rank*8 + file)) & 1 != 0{ 'X' } else { '.' };
+                write!(f, " {to_write}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  a b c d e f g h")
+    }
+}
 impl fmt::LowerHex for Bitboard{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "0x{:x}", self.0)?;
@@ -106,7 +127,7 @@ impl Bitboard{
     }
     pub fn bitscanforward_square(&self) -> Square{
         //assert!(self.0 != 0);
-        Square::try_from(self.bitscanforward()).unwrap()
+        Square::ALL[self.bitscanforward()]
     }
     pub const fn pop_lsb(&mut self) -> usize{
         //assert!(!self.is_empty());
@@ -117,7 +138,140 @@ impl Bitboard{
     }
     pub fn pop_lsb_square(&mut self) -> Square{
         //assert!(self.0 != 0);
-        Square::try_from(self.pop_lsb()).unwrap()
+        Square::ALL[self.pop_lsb()]
+    }
+
+    /// number of squares in the bitboard
+    pub const fn count(&self) -> u32{
+        self.0.count_ones()
+    }
+    pub const fn contains(&self, square: Square) -> bool{
+        (self.0 >> square as usize) & 1 == 1
+    }
+
+    /// the squares in the bitboard, lowest first
+    pub fn squares(self) -> Squares{
+        Squares(self)
+    }
+}
+
+/// Iterator over the squares of a bitboard, from A1 to H8
+#[derive(Clone, Debug)]
+pub struct Squares(Bitboard);
+impl Iterator for Squares{
+    type Item = Square;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item>{
+        if self.0.is_empty(){
+            None
+        }
+        else{
+            Some(self.0.pop_lsb_square())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>){
+        let count = self.0.count() as usize;
+        (count, Some(count))
+    }
+}
+impl ExactSizeIterator for Squares{}
+impl IntoIterator for Bitboard{
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Self::IntoIter{
+        self.squares()
+    }
+}
+impl FromIterator<Square> for Bitboard{
+    fn from_iter<T: IntoIterator<Item = Square>>(iter: T) -> Self{
+        let mut result = Bitboard::EMPTY;
+        for square in iter{
+            result |= Bitboard::from(square);
+        }
+        result
+    }
+}
+
+// shifts move every square one step in a direction, squares that
+// would wrap around to the other side of the board are dropped
+impl Bitboard{
+    pub const FILE_A: Bitboard = Bitboard(0x0101010101010101);
+    pub const FILE_H: Bitboard = Bitboard(0x8080808080808080);
+    pub const RANK_1: Bitboard = Bitboard(0xFF);
+    pub const RANK_8: Bitboard = Bitboard(0xFF << 56);
+
+    /// files A to H
+    pub const FILES: [Bitboard; 8] = {
+        let mut result = [Bitboard::EMPTY; 8];
+        let mut i = 0;
+        while i < 8{
+            result[i] = Bitboard(Self::FILE_A.0 << i);
+            i += 1;
+        }
+        result
+    };
+
+    /// ranks 1 to 8
+    pub const RANKS: [Bitboard; 8] = {
+        let mut result = [Bitboard::EMPTY; 8];
+        let mut i = 0;
+        while i < 8{
+            result[i] = Bitboard(Self::RANK_1.0 << (8*i));
+            i += 1;
+        }
+        result
+    };
+
+    /// diagonals going up and right, indexed by file - rank + 7,
+    /// so DIAGONALS[7] runs from A1 to H8
+    pub const DIAGONALS: [Bitboard; 15] = {
+        let mut result = [Bitboard::EMPTY; 15];
+        let mut square = 0;
+        while square < 64{
+            result[square % 8 + 7 - square / 8].0 |= 1 << square;
+            square += 1;
+        }
+        result
+    };
+
+    /// diagonals going up and left, indexed by file + rank,
+    /// so ANTI_DIAGONALS[7] runs from H1 to A8
+    pub const ANTI_DIAGONALS: [Bitboard; 15] = {
+        let mut result = [Bitboard::EMPTY; 15];
+        let mut square = 0;
+        while square < 64{
+            result[square % 8 + square / 8].0 |= 1 << square;
+            square += 1;
+        }
+        result
+    };
+
+    pub const fn north(self) -> Bitboard{
+        Bitboard(self.0 << 8)
+    }
+    pub const fn south(self) -> Bitboard{
+        Bitboard(self.0 >> 8)
+    }
+    pub const fn east(self) -> Bitboard{
+        Bitboard((self.0 << 1) & !Self::FILE_A.0)
+    }
+    pub const fn west(self) -> Bitboard{
+        Bitboard((self.0 >> 1) & !Self::FILE_H.0)
+    }
+    pub const fn north_east(self) -> Bitboard{
+        Bitboard((self.0 << 9) & !Self::FILE_A.0)
+    }
+    pub const fn north_west(self) -> Bitboard{
+        Bitboard((self.0 << 7) & !Self::FILE_H.0)
+    }
+    pub const fn south_east(self) -> Bitboard{
+        Bitboard((self.0 >> 7) & !Self::FILE_A.0)
+    }
+    pub const fn south_west(self) -> Bitboard{
+        Bitboard((self.0 >> 9) & !Self::FILE_H.0)
     }
 }
 impl From<Square> for Bitboard{
@@ -151,22 +305,106 @@ impl TryFrom<usize> for Square{
     type Error = ConversionError;
 
     fn try_from(value: usize) -> Result<Self, Self::Error> {
-        if value <= 63{
-            unsafe{
-                Ok(transmute::<usize, Square>(value))
-            }
+        // ALL is in discriminant order, see conversion_invariant_test
+        Square::ALL.get(value).copied().ok_or(Self::Error{})
+    }
+}
+
+impl_indexing!(Square);
+
+#[derive(Debug)]
+pub struct ParseSquareError{}
+impl fmt::Display for ParseSquareError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Problem encountered while reading square")
+    }
+}
+impl error::Error for ParseSquareError{}
+
+/// reads a square such as "e2" or "E2"
+impl FromStr for Square{
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>{
+        let &[file, rank] = s.as_bytes() else{
+            return Err(ParseSquareError{});
+        };
+
+        let file = file.to_ascii_lowercase().wrapping_sub(b'a');
+        let rank = rank.wrapping_sub(b'1');
+
+        Square::from_coords(file, rank).ok_or(ParseSquareError{})
+    }
+}
+impl Square{
+
+    /// 0 for the a file up to 7 for the h file
+    pub const fn file(self) -> u8{
+        (self as usize % 8) as u8
+    }
+
+    /// 0 for the first rank up to 7 for the eighth
+    pub const fn rank(self) -> u8{
+        (self as usize / 8) as u8
+    }
+
+    /// None if file or rank is off the board
+    pub fn from_coords(file: u8, rank: u8) -> Option<Square>{
+        if file < 8 && rank < 8{
+            Square::try_from((rank*8 + file) as usize).ok()
         }
         else{
-            Err(Self::Error{})
+            None
+        }
+    }
+
+    /// the square dx files right and dy ranks up, if it is on the board
+    pub fn offset(self, dx: i8, dy: i8) -> Option<Square>{
+        let file = self.file() as i8 + dx;
+        let rank = self.rank() as i8 + dy;
+
+        if (0..8).contains(&file) && (0..8).contains(&rank){
+            Square::from_coords(file as u8, rank as u8)
         }
+        else{
+            None
+        }
+    }
+
+    /// number of king moves between the squares
+    pub fn distance(self, other: Square) -> u8{
+        self.file().abs_diff(other.file()).max(self.rank().abs_diff(other.rank()))
+    }
+
+    /// number of rook steps between the squares
+    pub fn manhattan_distance(self, other: Square) -> u8{
+        self.file().abs_diff(other.file()) + self.rank().abs_diff(other.rank())
+    }
+
+    /// the same file on the opposite rank, A1 becomes A8
+    pub fn flip_vertical(self) -> Square{
+        Square::ALL[self as usize ^ 56]
+    }
+
+    /// displays as "e2" instead of "E2"
+    pub fn lowercase(self) -> LowercaseSquare{
+        LowercaseSquare(self)
     }
 }
 
-impl_indexing!(Square);
+/// A square that displays in lowercase, see Square::lowercase
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LowercaseSquare(pub Square);
+impl fmt::Display for LowercaseSquare{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.0.file()) as char, self.0.rank() + 1)
+    }
+}
 
+/// The side a piece belongs to
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum Color{
+pub enum Color{
     White = 0,
     Black = 8,
 }
@@ -196,21 +434,19 @@ impl TryFrom<usize> for Color{
     type Error = ConversionError;
 
     fn try_from(value: usize) -> Result<Self, Self::Error>{
-        if value == 0 || value == 8{
-            unsafe{
-                Ok(transmute::<usize, Color>(value))
-            }
-        }
-        else{
-            Err(Self::Error{})
+        match value{
+            0 => Ok(Color::White),
+            8 => Ok(Color::Black),
+            _ => Err(Self::Error{}),
         }
     }
 }
 
 num_and_all!{
+/// A kind of piece, without its color
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum Piece{
+pub enum Piece{
     Empty = 0,
     Stradler = 1,
     Coordinator = 2,
@@ -240,14 +476,7 @@ impl TryFrom<usize> for Piece{
     type Error = ConversionError;
 
     fn try_from(value: usize) -> Result<Self, Self::Error>{
-        if value <= 7{
-            unsafe{
-                Ok(transmute::<usize, Piece>(value))
-            }
-        }
-        else{
-            Err(Self::Error{})
-        }
+        Piece::ALL.get(value).copied().ok_or(Self::Error{})
     }
 }
 
@@ -277,6 +506,137 @@ mod test{
         }
     }
 
+    #[test]
+    fn bitboard_iter_test(){
+        let bb = Bitboard(1 << 3 | 1 << 17 | 1 << 63);
+
+        assert_eq!(bb.count(), 3);
+        assert_eq!(bb.squares().len(), 3);
+        assert!(bb.contains(Square::B3));
+        assert!(!bb.contains(Square::C3));
+
+        let squares: Vec<Square> = bb.into_iter().collect();
+        assert_eq!(squares, [Square::D1, Square::B3, Square::H8]);
+        assert_eq!(squares.into_iter().collect::<Bitboard>(), bb);
+        assert_eq!(Square::ALL.into_iter().collect::<Bitboard>(), Bitboard(u64::MAX));
+    }
+
+    #[test]
+    fn bitboard_shift_test(){
+        let a1 = Bitboard::from(Square::A1);
+        let h8 = Bitboard::from(Square::H8);
+        let d4 = Bitboard::from(Square::D4);
+
+        assert_eq!(d4.north(), Bitboard::from(Square::D5));
+        assert_eq!(d4.south_west(), Bitboard::from(Square::C3));
+        assert_eq!(d4.north_east().north_east(), Bitboard::from(Square::F6));
+
+        // nothing wraps around the edges
+        assert!(a1.west().is_empty());
+        assert!(a1.south().is_empty());
+        assert!(a1.north_west().is_empty());
+        assert!(h8.east().is_empty());
+        assert!(h8.north().is_empty());
+        assert!(h8.south_east().is_empty());
+        assert_eq!(Bitboard::FILE_H.east(), Bitboard::EMPTY);
+        assert_eq!(Bitboard::FILE_A.east(), Bitboard::FILES[1]);
+        assert_eq!(Bitboard::RANKS[7], Bitboard::RANK_8);
+    }
+
+    #[test]
+    fn bitboard_lines_test(){
+        let a1_h8: Bitboard = [Square::A1, Square::B2, Square::C3, Square::D4, Square::E5, Square::F6, Square::G7, Square::H8].into_iter().collect();
+        assert_eq!(Bitboard::DIAGONALS[7], a1_h8);
+        assert_eq!(Bitboard::DIAGONALS[0], Bitboard::from(Square::A8));
+        assert_eq!(Bitboard::ANTI_DIAGONALS[7].count(), 8);
+        assert!(Bitboard::ANTI_DIAGONALS[7].contains(Square::H1));
+        assert!(Bitboard::ANTI_DIAGONALS[7].contains(Square::A8));
+
+        let all = Bitboard::DIAGONALS.iter().fold(Bitboard::EMPTY, |acc, d| acc | *d);
+        assert_eq!(all, Bitboard(u64::MAX));
+    }
+
+    #[test]
+    fn bitboard_display_test(){
+        let bb = Bitboard::from(Square::A1) | Bitboard::from(Square::H8);
+        assert_eq!(bb.to_string(), "\
+8 . . . . . . . X
+7 . . . . . . . .
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . . . . .
+3 . . . . . . . .
+2 . . . . . . . .
+1 X . . . . . . .
+  a b c d e f g h
+");
+    }
+
+    #[test]
+    fn square_parse_test(){
+        assert_eq!("e2".parse::<Square>().unwrap(), Square::E2);
+        assert_eq!("E2".parse::<Square>().unwrap(), Square::E2);
+        assert_eq!("h8".parse::<Square>().unwrap(), Square::H8);
+
+        for bad in ["", "e", "e9", "i1", "e22", "2e", "é2"]{
+            assert!(bad.parse::<Square>().is_err());
+        }
+
+        for square in Square::ALL{
+            assert_eq!(square.to_string().parse::<Square>().unwrap(), square);
+            assert_eq!(square.lowercase().to_string().parse::<Square>().unwrap(), square);
+            assert_eq!(Square::from_coords(square.file(), square.rank()), Some(square));
+        }
+
+        assert_eq!(Square::E2.lowercase().to_string(), "e2");
+    }
+
+    #[test]
+    fn square_geometry_test(){
+        assert_eq!(Square::C3.file(), 2);
+        assert_eq!(Square::C3.rank(), 2);
+        assert_eq!(Square::from_coords(8, 0), None);
+
+        assert_eq!(Square::E2.offset(1, 2), Some(Square::F4));
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::H8.offset(0, 1), None);
+
+        assert_eq!(Square::A1.distance(Square::H8), 7);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+        assert_eq!(Square::E4.distance(Square::F6), 2);
+        assert_eq!(Square::E4.manhattan_distance(Square::F6), 3);
+
+        assert_eq!(Square::A1.flip_vertical(), Square::A8);
+        assert_eq!(Square::E2.flip_vertical(), Square::E7);
+        for square in Square::ALL{
+            assert_eq!(square.flip_vertical().flip_vertical(), square);
+        }
+    }
+
+    // the conversions and Move's getters index ALL by discriminant
+    #[test]
+    fn conversion_invariant_test(){
+        for (i, square) in Square::ALL.into_iter().enumerate(){
+            assert_eq!(square as usize, i);
+            assert_eq!(Square::try_from(i).unwrap(), square);
+        }
+        for (i, piece) in Piece::ALL.into_iter().enumerate(){
+            assert_eq!(piece as usize, i);
+            assert_eq!(Piece::try_from(i).unwrap(), piece);
+        }
+
+        assert_eq!(Color::try_from(0).unwrap(), Color::White);
+        assert_eq!(Color::try_from(8).unwrap(), Color::Black);
+
+        for bad in [64, 65, usize::MAX]{
+            assert!(Square::try_from(bad).is_err());
+        }
+        for bad in [1, 7, 9, 16]{
+            assert!(Color::try_from(bad).is_err());
+        }
+        assert!(Piece::try_from(8).is_err());
+    }
+
     #[test]
     fn bitboard_get_bit_test(){
         let bb = Bitboard(u64::MAX);
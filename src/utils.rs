@@ -65,6 +65,25 @@ macro_rules! impl_indexing {
 }
 pub(crate) use impl_indexing;
 
+// declares a lookup table that is computed during compilation, or the
+// first time it is used when the lazy-tables feature is on. either way
+// it is indexed the same. with compact-tables it isn't there at all
+macro_rules! lookup_table{
+    (
+        $(#[$meta:meta])*
+        $vis:vis static $name:ident: $ty:ty = $init:expr;
+    ) => {
+        $(#[$meta])*
+        #[cfg(not(any(feature = "lazy-tables", feature = "compact-tables")))]
+        $vis static $name: $ty = $init;
+
+        $(#[$meta])*
+        #[cfg(all(feature = "lazy-tables", not(feature = "compact-tables")))]
+        $vis static $name: ::std::sync::LazyLock<$ty> = ::std::sync::LazyLock::new(|| $init);
+    };
+}
+pub(crate) use lookup_table;
+
 
 // source: xorshift* from stockfish (it's also the example used by wikipedia)
 pub(crate) struct PRNG{
@@ -72,6 +91,12 @@ pub(crate) struct PRNG{
     seed: u64
 }
 impl PRNG{
+    /// seed must not be 0, xorshift never leaves the all zero state
+    pub(crate) const fn new(seed: u64) -> Self{
+        assert!(seed != 0);
+        PRNG{ seed }
+    }
+
     pub(crate) const fn rand64(&mut self) -> u64{
         self.seed ^= self.seed >> 12;
         self.seed ^= self.seed << 25;
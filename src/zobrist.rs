@@ -0,0 +1,100 @@
+use crate::types::{Color, Piece, Square};
+use crate::rules::Rules;
+use crate::utils::PRNG;
+
+const SEED: u64 = 3262394871;
+
+// indexed the same way as Position::bitboards, (color | piece)
+// the keys for the empty "pieces" (0 and 8) are left as 0
+static PIECE_KEYS: [[u64; 64]; 16] = {
+    let mut result: [[u64; 64]; 16] = [[0; 64]; 16];
+    let mut rng = PRNG::new(SEED);
+
+    let mut i = 0;
+    while i < 16{
+
+        if i != 0 && i != 8{
+            let mut j = 0;
+            while j < 64{
+                result[i][j] = rng.rand64();
+                j += 1;
+            }
+        }
+
+        i += 1;
+    }
+
+    result
+};
+
+// xored in when black is to play
+static SIDE_KEY: u64 = {
+    let mut rng = PRNG::new(SEED ^ 0xFFFFFFFF);
+    rng.rand64()
+};
+
+// xored in for each rule that changes how pieces move and is set
+// differently from Rules::default(), so hashes of positions played by the
+// default rules stay the same. immobilizer_on_left only picks the starting
+// position, the same position plays the same either way so it has no key
+static RULE_KEYS: [u64; 2] = {
+    let mut rng = PRNG::new(SEED ^ 0xFFFF0000);
+    [rng.rand64(), rng.rand64()]
+};
+
+#[inline]
+pub(crate) fn piece_key(color: Color, piece: Piece, square: Square) -> u64{
+    PIECE_KEYS[color | piece][square]
+}
+
+#[inline]
+pub(crate) fn side_key() -> u64{
+    SIDE_KEY
+}
+
+pub(crate) fn rules_key(rules: Rules) -> u64{
+    let default = Rules::default();
+    let mut result = 0;
+
+    if rules.chameleon_immobilizes_immobilizer != default.chameleon_immobilizes_immobilizer{
+        result ^= RULE_KEYS[0];
+    }
+    if rules.springer_multi_capture != default.springer_multi_capture{
+        result ^= RULE_KEYS[1];
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test{
+
+    use super::*;
+
+    #[test]
+    fn unique_keys_test(){
+
+        let mut keys: Vec<u64> = PIECE_KEYS.iter()
+            .flatten()
+            .copied()
+            .filter(|k| *k != 0)
+            .collect();
+        keys.push(SIDE_KEY);
+        keys.extend(RULE_KEYS);
+
+        let total = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+
+        assert_eq!(total, 14*64 + 3);
+        assert_eq!(keys.len(), total);
+    }
+
+    #[test]
+    fn rules_key_test(){
+        assert_eq!(rules_key(Rules::HYPERCHESS), 0);
+        assert_eq!(rules_key(Rules{ immobilizer_on_left: true, ..Rules::HYPERCHESS }), 0);
+        assert_eq!(rules_key(Rules::ULTIMA), rules_key(Rules::BAROQUE));
+        assert_ne!(rules_key(Rules::ULTIMA), 0);
+    }
+}
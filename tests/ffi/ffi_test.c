@@ -0,0 +1,77 @@
+/* compiled and run by tests/ffi_tests.rs against the cdylib built with
+   the ffi feature, exits with the line of the first failed check */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "hmg.h"
+
+#define CHECK(x) do{ if(!(x)){ fprintf(stderr, "check failed: %s\n", #x); return __LINE__; } }while(0)
+
+int main(void){
+
+    CHECK(hmg_api_version() == HMG_API_VERSION);
+
+    CHECK(hmg_position_from_fen("not a fen") == NULL);
+    CHECK(hmg_position_from_fen(NULL) == NULL);
+
+    HmgPosition *start = hmg_position_start();
+    CHECK(start != NULL);
+
+    uint32_t moves[256];
+    size_t count = hmg_legal_moves(start, moves, 256);
+    CHECK(count == 32);
+    CHECK(hmg_legal_moves(start, NULL, 0) == count);
+
+    char fen[128];
+    size_t length = hmg_to_fen(start, fen, sizeof fen);
+    CHECK(length == strlen(fen));
+    CHECK(strcmp(fen, "unbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNU w 0 1") == 0);
+
+    CHECK(hmg_piece_at(start, 0) == 'R');
+    CHECK(hmg_piece_at(start, 63) == 'r');
+    CHECK(hmg_piece_at(start, 27) == '.');
+    CHECK(hmg_piece_at(start, 64) == 0);
+
+    /* play every move and take it back */
+    for(size_t i = 0; i < count; i++){
+        CHECK(hmg_make_move(start, moves[i]) == 0);
+        CHECK(hmg_piece_at(start, hmg_move_from(moves[i])) == '.');
+        CHECK(hmg_piece_at(start, hmg_move_to(moves[i])) != '.');
+        CHECK(hmg_unmake_move(start) == 0);
+    }
+    CHECK(hmg_unmake_move(start) == -1);
+
+    char after[128];
+    hmg_to_fen(start, after, sizeof after);
+    CHECK(strcmp(fen, after) == 0);
+
+    /* a springer leap from d2 over d4, which isn't legal once played */
+    HmgPosition *leap = hmg_position_from_fen("k7/8/8/8/3n4/8/3N4/K7 w 0 1");
+    CHECK(leap != NULL);
+
+    count = hmg_legal_moves(leap, moves, 256);
+    uint32_t capture = 0;
+    for(size_t i = 0; i < count; i++){
+        if(hmg_move_from(moves[i]) == 11 && hmg_move_to(moves[i]) == 35){
+            capture = moves[i];
+        }
+    }
+    CHECK(capture != 0);
+    CHECK(hmg_make_move(leap, capture) == 0);
+    CHECK(hmg_piece_at(leap, 27) == '.');
+    CHECK(hmg_make_move(leap, capture) == -1);
+
+    /* a short buffer still gets a terminated prefix */
+    char short_fen[8];
+    length = hmg_to_fen(leap, short_fen, sizeof short_fen);
+    CHECK(length > sizeof short_fen);
+    CHECK(strcmp(short_fen, "k7/8/8/") == 0);
+
+    hmg_position_free(leap);
+    hmg_position_free(start);
+    hmg_position_free(NULL);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,64 @@
+// builds the cdylib with the ffi feature, then tests/ffi/ffi_test.c
+// against it and include/hmg.h, and runs the program. part of the
+// default test run, it is skipped if there is no C compiler
+
+#![cfg(unix)]
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_test_program(){
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let target_tmpdir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let compiler = std::env::var("CC").unwrap_or("cc".to_string());
+
+    match Command::new(&compiler).arg("--version").output(){
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!("skipping the C test program, there is no C compiler {compiler}");
+            return;
+        },
+        result => assert!(result.unwrap().status.success(), "couldn't run the C compiler {compiler}"),
+    }
+
+    // a target directory of its own, the one running this test is locked.
+    // lazy-tables only makes the build quicker, the interface is the same
+    let target_dir = target_tmpdir.join("cdylib");
+    let status = Command::new(std::env::var("CARGO").unwrap_or("cargo".to_string()))
+        .arg("build")
+        .arg("--lib")
+        .arg("--features").arg("ffi,lazy-tables")
+        .arg("--manifest-path").arg(manifest_dir.join("Cargo.toml"))
+        .arg("--target-dir").arg(&target_dir)
+        .status()
+        .expect("couldn't run cargo");
+
+    assert!(status.success(), "building the cdylib failed");
+
+    let library_dir = target_dir.join("debug");
+    let program = target_tmpdir.join("ffi_test");
+
+    let status = Command::new(&compiler)
+        .arg(manifest_dir.join("tests/ffi/ffi_test.c"))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I").arg(manifest_dir.join("include"))
+        .arg("-L").arg(&library_dir)
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .arg("-lhmg")
+        .arg("-o").arg(&program)
+        .status()
+        .expect("couldn't run the C compiler");
+
+    assert!(status.success(), "compiling ffi_test.c failed");
+
+    // cargo puts its own deps on the library path, and the cdylib built
+    // there for this test run doesn't have the ffi feature
+    let output = Command::new(&program).env("LD_LIBRARY_PATH", &library_dir).output().unwrap();
+
+    assert!(output.status.success(), "ffi_test failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -1,6 +1,6 @@
 use core::panic;
 use std::{fs::File, io::Read};
-use hmg::{Position, MoveList};
+use hmg::{Position, MoveList, Rules};
 use serde::{Serialize, Deserialize};
 use serde_json;
 
@@ -9,6 +9,9 @@ struct TestPosition{
     fen: String,
     nodes: Vec<usize>,
     depth: usize,
+    /// rule set the position is played by, Rules::default() if missing
+    #[serde(default)]
+    rules: Option<String>,
 }
 
 fn load_test_suite() -> Vec<TestPosition>{
@@ -79,6 +82,11 @@ fn run_test_suite(){
     
         let mut test_position = Position::from_FEN(&test.fen).unwrap();
 
+        if let Some(rules) = &test.rules{
+            println!("Rules: {rules}");
+            test_position.set_rules(rules.parse::<Rules>().unwrap());
+        }
+
         run_test_position(&mut test_position, 0, test.depth, &mut move_counter);
         
         println!("Expected: {:?}", test.nodes);
@@ -104,4 +112,54 @@ fn run_test_suite(){
     if passed < total_tests{
         panic!();
     }
-}
\ No newline at end of file
+}
+
+/// perft depth the flipped and mirrored positions are checked to,
+/// deeper counts are left to run_test_suite
+const SYMMETRY_DEPTH: usize = 3;
+
+/// checkmates among the positions up to depth plies from position
+fn count_checkmates(position: &mut Position, depth: usize) -> usize{
+
+    let mut result = position.is_checkmate() as usize;
+
+    if depth == 0{
+        return result;
+    }
+
+    for m in position.generate_moves(){
+        if !position.is_move_legal(m){
+            continue;
+        }
+        position.make_move(m);
+        result += count_checkmates(position, depth-1);
+        position.unmake_move(m);
+    }
+
+    result
+}
+
+#[test]
+fn symmetry_test_suite(){
+
+    for test in load_test_suite(){
+
+        let mut position = Position::from_FEN(&test.fen).unwrap();
+
+        if let Some(rules) = &test.rules{
+            position.set_rules(rules.parse::<Rules>().unwrap());
+        }
+
+        let depth = test.depth.min(SYMMETRY_DEPTH);
+        let checkmates = count_checkmates(&mut position, depth-1);
+
+        for (name, mut transformed) in [("flipped", position.flipped()), ("mirrored", position.mirrored_horizontal())]{
+
+            let mut move_counter: Vec<usize> = vec![0; depth+1];
+            run_test_position(&mut transformed, 0, depth, &mut move_counter);
+
+            assert_eq!(move_counter, test.nodes[..=depth], "{name} {}", test.fen);
+            assert_eq!(count_checkmates(&mut transformed, depth-1), checkmates, "{name} {}", test.fen);
+        }
+    }
+}